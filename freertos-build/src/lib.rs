@@ -36,6 +36,8 @@ pub struct Builder {
     max_task_name_len: Option<usize>,
     queue_registry_size: Option<usize>,
     check_for_stack_overflow: Option<u8>,
+    run_time_stats_dwt: bool,
+    tickless_idle: Option<u32>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -95,6 +97,8 @@ impl Default for Builder {
             max_task_name_len: None,
             queue_registry_size: None,
             check_for_stack_overflow: None,
+            run_time_stats_dwt: false,
+            tickless_idle: None,
         }
     }
 }
@@ -257,6 +261,26 @@ impl Builder {
         self.check_for_stack_overflow = Some(v)
     }
 
+    /// Stop the tick while idle for at least `expected_idle_time_before_sleep` ticks,
+    /// which must not be less than 2. The `SLEEP_HOOKS` of freertos-next run around the sleep.
+    /// Needs a port with a 32-bit `TickType_t`.
+    ///
+    /// https://www.freertos.org/low-power-tickless-rtos.html
+    pub fn tickless_idle(&mut self, expected_idle_time_before_sleep: u32) {
+        assert!(
+            expected_idle_time_before_sleep >= 2,
+            "tickless idle needs an expected idle time of at least 2 ticks, got {}",
+            expected_idle_time_before_sleep
+        );
+        self.tickless_idle = Some(expected_idle_time_before_sleep);
+    }
+
+    /// Generate run-time stats counted in microseconds by the DWT cycle counter,
+    /// see `DwtInstant` of freertos-next. Needs a Cortex-M3/M4/M7/M33 and `cpu_clock`.
+    pub fn run_time_stats_dwt(&mut self) {
+        self.run_time_stats_dwt = true;
+    }
+
     fn freertos_include_dir(&self) -> PathBuf {
         self.freertos_dir.join("include")
     }
@@ -451,6 +475,14 @@ impl Builder {
         if let Some(v) = self.check_for_stack_overflow {
             set_define!(cc, "configCHECK_FOR_STACK_OVERFLOW", v);
         }
+        if let Some(v) = self.tickless_idle {
+            set_define!(cc, "configUSE_TICKLESS_IDLE", 1);
+            set_define!(cc, "configEXPECTED_IDLE_TIME_BEFORE_SLEEP", v);
+        }
+        if self.run_time_stats_dwt {
+            set_define!(cc, "configGENERATE_RUN_TIME_STATS", 1);
+            set_define!(cc, "__RUN_TIME_STATS_DWT", 1);
+        }
         setup_all_define(&mut cc);
 
         println!(
@@ -524,6 +556,7 @@ fn add_include_with_rerun<P: AsRef<Path>>(build: &mut Build, dir: P) {
 
 fn setup_all_define(cc: &mut cc::Build) {
     sync_define(cc, "__IS_CORTEX_M");
+    sync_define(cc, "__IS_HOSTED");
     sync_define(cc, "INCLUDE_vTaskDelete");
     sync_define(cc, "INCLUDE_vTaskDelayUntil");
     sync_define(cc, "INCLUDE_uxTaskGetStackHighWaterMark");
@@ -532,6 +565,9 @@ fn setup_all_define(cc: &mut cc::Build) {
     sync_define(cc, "configUSE_RECURSIVE_MUTEXES");
     sync_define(cc, "configUSE_COUNTING_SEMAPHORES");
     sync_define(cc, "configUSE_TRACE_FACILITY");
+    sync_define(cc, "configSUPPORT_STATIC_ALLOCATION");
+    sync_define(cc, "INCLUDE_vTaskPrioritySet");
+    sync_define(cc, "INCLUDE_uxTaskPriorityGet");
 }
 
 fn sync_define(cc: &mut cc::Build, def: &str) {
@@ -45,7 +45,6 @@
     is part of the win32 thread. */
 #define configUSE_ALTERNATIVE_API                     0
 #define configUSE_QUEUE_SETS                          1
-#define configSUPPORT_STATIC_ALLOCATION               1
 #define configINITIAL_TICK_COUNT                      ((TickType_t)0) /* For test. */
 #define configSTREAM_BUFFER_TRIGGER_LEVEL_TEST_MARGIN 1               /* As there are a lot of tasks running. */
 
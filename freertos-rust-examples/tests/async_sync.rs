@@ -0,0 +1,270 @@
+//! Async waits on queues, semaphores, mutexes and event groups, fed by blocking tasks.
+//!
+//! A plain binary like the other kernel tests. The `*_from_isr` calls run on a task,
+//! which the host port allows, to check the wakes deferred to the timer task.
+
+use freertos_next::*;
+use std::future::Future;
+use std::pin::pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::task::{Context, Poll, Wake, Waker};
+
+static QUEUE_SUM: AtomicU32 = AtomicU32::new(0);
+static SENT: AtomicU32 = AtomicU32::new(0);
+static TAKEN: AtomicU32 = AtomicU32::new(0);
+static LOCKED: AtomicU32 = AtomicU32::new(0);
+static EVENTS: AtomicU32 = AtomicU32::new(0);
+
+struct Flag(AtomicBool);
+
+impl Wake for Flag {
+    fn wake(self: Arc<Self>) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+fn settle() {
+    CurrentTask::delay(Duration::ms(10));
+}
+
+fn blocking_sender_feeds_async_receiver(spawner: &Spawner<8>) {
+    let queue = Arc::new(Queue::<u32>::new(4).unwrap());
+    let receiver = queue.clone();
+    spawner
+        .spawn(async move {
+            loop {
+                let value = receiver.receive_async().await;
+                QUEUE_SUM.fetch_add(value, Ordering::SeqCst);
+            }
+        })
+        .unwrap();
+    settle();
+
+    for value in 1..=3 {
+        queue.send(value, Duration::infinite()).unwrap();
+        settle();
+    }
+    assert_eq!(QUEUE_SUM.load(Ordering::SeqCst), 6);
+
+    let mut context = InterruptContext::new();
+    queue.send_from_isr(&mut context, 10).unwrap();
+    drop(context);
+    settle();
+    assert_eq!(QUEUE_SUM.load(Ordering::SeqCst), 16);
+    println!("blocking_sender_feeds_async_receiver [ok]");
+}
+
+fn async_sender_waits_for_space(spawner: &Spawner<8>) {
+    let queue = Arc::new(Queue::<u32>::new(1).unwrap());
+    let sender = queue.clone();
+    spawner
+        .spawn(async move {
+            for value in 0..3 {
+                sender.send_async(value).await;
+                SENT.fetch_add(1, Ordering::SeqCst);
+            }
+        })
+        .unwrap();
+    settle();
+    assert_eq!(SENT.load(Ordering::SeqCst), 1);
+
+    for expected in 0..3 {
+        assert_eq!(queue.receive(Duration::ms(100)).unwrap(), expected);
+        settle();
+    }
+    assert_eq!(SENT.load(Ordering::SeqCst), 3);
+    println!("async_sender_waits_for_space [ok]");
+}
+
+fn semaphore_wakes_async_taker(spawner: &Spawner<8>) {
+    let semaphore = Arc::new(Semaphore::new_binary().unwrap());
+    let taker = semaphore.clone();
+    spawner
+        .spawn(async move {
+            loop {
+                taker.take_async().await;
+                TAKEN.fetch_add(1, Ordering::SeqCst);
+            }
+        })
+        .unwrap();
+    settle();
+    assert_eq!(TAKEN.load(Ordering::SeqCst), 0);
+
+    semaphore.give();
+    settle();
+    assert_eq!(TAKEN.load(Ordering::SeqCst), 1);
+
+    let mut context = InterruptContext::new();
+    assert!(semaphore.give_from_isr(&mut context));
+    drop(context);
+    settle();
+    assert_eq!(TAKEN.load(Ordering::SeqCst), 2);
+    println!("semaphore_wakes_async_taker [ok]");
+}
+
+fn isr_wake_survives_full_pend_slots(spawner: &Spawner<8>) {
+    static WOKEN: AtomicU32 = AtomicU32::new(0);
+
+    let semaphore = Arc::new(Semaphore::new_binary().unwrap());
+    let taker = semaphore.clone();
+    spawner
+        .spawn(async move {
+            taker.take_async().await;
+            WOKEN.store(1, Ordering::SeqCst);
+        })
+        .unwrap();
+    settle();
+
+    // Take every pend slot, so the wake can't be deferred to the timer task.
+    let mut context = InterruptContext::new();
+    while pend_function_call_from_isr(&mut context, || ()).is_ok() {}
+    assert!(semaphore.give_from_isr(&mut context));
+    drop(context);
+    settle();
+    assert_eq!(WOKEN.load(Ordering::SeqCst), 1);
+    println!("isr_wake_survives_full_pend_slots [ok]");
+}
+
+fn unlock_wakes_async_locker(spawner: &Spawner<8>) {
+    let mutex = Arc::new(Mutex::new(0u32).unwrap());
+    let locker = mutex.clone();
+    let guard = mutex.lock(Duration::infinite()).unwrap();
+    spawner
+        .spawn(async move {
+            let mut value = locker.lock_async().await.unwrap();
+            *value += 1;
+            LOCKED.store(*value, Ordering::SeqCst);
+        })
+        .unwrap();
+    settle();
+    assert_eq!(LOCKED.load(Ordering::SeqCst), 0);
+
+    drop(guard);
+    settle();
+    assert_eq!(LOCKED.load(Ordering::SeqCst), 1);
+    assert_eq!(*mutex.lock(Duration::zero()).unwrap(), 1);
+    println!("unlock_wakes_async_locker [ok]");
+}
+
+fn event_bits_wake_async_waiter(spawner: &Spawner<8>) {
+    let events = Arc::new(EventGroup::new().unwrap());
+    let waiter = events.clone();
+    spawner
+        .spawn(async move {
+            let bits = waiter
+                .wait_all(EventBits(0b11))
+                .clear_on_exit()
+                .wait_async()
+                .await;
+            EVENTS.store(bits.0, Ordering::SeqCst);
+        })
+        .unwrap();
+    settle();
+
+    events.set(EventBits(0b01));
+    settle();
+    assert_eq!(EVENTS.load(Ordering::SeqCst), 0);
+
+    events.set(EventBits(0b10));
+    settle();
+    assert_eq!(EVENTS.load(Ordering::SeqCst), 0b11);
+    assert_eq!(events.get(), EventBits(0));
+    println!("event_bits_wake_async_waiter [ok]");
+}
+
+fn isr_bits_wake_after_they_are_set(spawner: &Spawner<8>) {
+    let events = Arc::new(EventGroup::new().unwrap());
+    let waiter = events.clone();
+    spawner
+        .spawn(async move {
+            let bits = waiter.wait_any(EventBits(0b100)).wait_async().await;
+            EVENTS.store(bits.0, Ordering::SeqCst);
+        })
+        .unwrap();
+    settle();
+
+    let mut context = InterruptContext::new();
+    events
+        .set_bits_from_isr(&mut context, EventBits(0b100))
+        .unwrap();
+    drop(context);
+    settle();
+    assert_eq!(EVENTS.load(Ordering::SeqCst), 0b100);
+    println!("isr_bits_wake_after_they_are_set [ok]");
+}
+
+fn isr_bits_fail_with_full_pend_slots() {
+    let events = EventGroup::new().unwrap();
+    let woken = Arc::new(Flag(AtomicBool::new(false)));
+    let waker = Waker::from(woken.clone());
+    let mut cx = Context::from_waker(&waker);
+    let mut wait = pin!(events.wait_any(EventBits(1)).wait_async());
+    assert!(wait.as_mut().poll(&mut cx).is_pending());
+
+    // The bits and the wake need one pend slot, so neither happens.
+    let mut context = InterruptContext::new();
+    while pend_function_call_from_isr(&mut context, || ()).is_ok() {}
+    assert_eq!(
+        events.set_bits_from_isr(&mut context, EventBits(1)),
+        Err(FreeRtosError::QueueFull)
+    );
+    drop(context);
+    settle();
+    assert!(!woken.0.load(Ordering::SeqCst));
+    assert_eq!(events.get(), EventBits(0));
+    println!("isr_bits_fail_with_full_pend_slots [ok]");
+}
+
+fn isr_bits_wake_a_later_waiter() {
+    let events = EventGroup::new().unwrap();
+    let woken = Arc::new(Flag(AtomicBool::new(false)));
+    let waker = Waker::from(woken.clone());
+    let mut cx = Context::from_waker(&waker);
+    let mut wait = pin!(events.wait_any(EventBits(1)).wait_async());
+    {
+        // Keep the timer task from setting the bits before the future registers.
+        let scheduler = SuspendScheduler::new(());
+        let _suspended = scheduler.lock();
+        let mut context = InterruptContext::new();
+        events
+            .set_bits_from_isr(&mut context, EventBits(1))
+            .unwrap();
+        assert!(wait.as_mut().poll(&mut cx).is_pending());
+    }
+    settle();
+    assert!(woken.0.load(Ordering::SeqCst));
+    assert_eq!(wait.as_mut().poll(&mut cx), Poll::Ready(EventBits(1)));
+    println!("isr_bits_wake_a_later_waiter [ok]");
+}
+
+fn main() {
+    Task::new()
+        .name("tests")
+        .stack_size(512)
+        .priority(TaskPriority(2))
+        .start(|_| {
+            let executor = Executor::<8>::new();
+            let spawner = executor.spawner();
+            executor
+                .start(
+                    Task::new()
+                        .name("executor")
+                        .stack_size(512)
+                        .priority(TaskPriority(3)),
+                )
+                .unwrap();
+            blocking_sender_feeds_async_receiver(&spawner);
+            async_sender_waits_for_space(&spawner);
+            semaphore_wakes_async_taker(&spawner);
+            isr_wake_survives_full_pend_slots(&spawner);
+            unlock_wakes_async_locker(&spawner);
+            event_bits_wake_async_waiter(&spawner);
+            isr_bits_wake_after_they_are_set(&spawner);
+            isr_bits_fail_with_full_pend_slots();
+            isr_bits_wake_a_later_waiter();
+            std::process::exit(0);
+        })
+        .unwrap();
+    FreeRtosUtils::start_scheduler();
+}
@@ -0,0 +1,121 @@
+//! `Barrier` and `Rendezvous` on the host port, a plain binary like the other kernel tests.
+
+use freertos_next::*;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+fn spawn(name: &str, priority: u8, f: impl FnOnce() + Send + 'static) {
+    Task::new()
+        .name(name)
+        .stack_size(256)
+        .priority(TaskPriority(priority))
+        .start(move |_| {
+            f();
+            loop {
+                CurrentTask::delay(Duration::infinite());
+            }
+        })
+        .unwrap();
+}
+
+fn barrier_releases_all_with_one_leader() {
+    static LEADERS: AtomicU32 = AtomicU32::new(0);
+
+    let barrier = Arc::new(Barrier::new(2).unwrap());
+    let other = barrier.clone();
+    spawn("other", 3, move || {
+        for _ in 0..2 {
+            if other.wait(Duration::infinite()).unwrap().is_leader() {
+                LEADERS.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+    });
+    for _ in 0..2 {
+        if barrier.wait(Duration::ms(100)).unwrap().is_leader() {
+            LEADERS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+    CurrentTask::delay(Duration::ms(5));
+    assert_eq!(LEADERS.load(Ordering::SeqCst), 2);
+    println!("barrier_releases_all_with_one_leader [ok]");
+}
+
+fn barrier_timeout_withdraws_arrival() {
+    let barrier = Barrier::new(2).unwrap();
+    assert_eq!(
+        barrier.wait(Duration::ms(5)).err(),
+        Some(FreeRtosError::Timeout)
+    );
+    // The withdrawn arrival doesn't count for the next round.
+    assert_eq!(
+        barrier.wait(Duration::ms(5)).err(),
+        Some(FreeRtosError::Timeout)
+    );
+    println!("barrier_timeout_withdraws_arrival [ok]");
+}
+
+fn timeout_racing_last_arrival_completes_round() {
+    static LEADER_DONE: AtomicU32 = AtomicU32::new(0);
+
+    let barrier = Arc::new(Barrier::new(2).unwrap());
+    let leader = barrier.clone();
+    // Wakes on the tick our wait times out, and runs first as it has the higher priority.
+    spawn("leader", 3, move || {
+        CurrentTask::delay(Duration::ticks(10));
+        leader.wait(Duration::ms(100)).unwrap();
+        LEADER_DONE.store(1, Ordering::SeqCst);
+        leader.wait(Duration::infinite()).unwrap();
+        LEADER_DONE.store(2, Ordering::SeqCst);
+    });
+    match barrier.wait(Duration::ticks(10)) {
+        Ok(result) => assert!(!result.is_leader()),
+        // The leader was late, it arrives for a round of its own.
+        Err(e) => {
+            assert_eq!(e, FreeRtosError::Timeout);
+            barrier.wait(Duration::ms(100)).unwrap();
+        }
+    }
+    assert!(LEADER_DONE.load(Ordering::SeqCst) >= 1);
+
+    // A stale bit would complete this round early or block it.
+    barrier.wait(Duration::ms(100)).unwrap();
+    CurrentTask::delay(Duration::ms(5));
+    assert_eq!(LEADER_DONE.load(Ordering::SeqCst), 2);
+    println!("timeout_racing_last_arrival_completes_round [ok]");
+}
+
+fn rendezvous_swaps_values() {
+    static GOT: AtomicU32 = AtomicU32::new(0);
+
+    let rendezvous = Arc::new(Rendezvous::<u32>::new().unwrap());
+    let other = rendezvous.clone();
+    spawn("swapper", 3, move || {
+        GOT.store(
+            other.exchange(1, Duration::infinite()).unwrap(),
+            Ordering::SeqCst,
+        );
+    });
+    assert_eq!(rendezvous.exchange(2, Duration::ms(100)).unwrap(), 1);
+    CurrentTask::delay(Duration::ms(5));
+    assert_eq!(GOT.load(Ordering::SeqCst), 2);
+
+    let err = rendezvous.exchange(3, Duration::ms(5)).unwrap_err();
+    assert_eq!(err.into_item(), 3);
+    println!("rendezvous_swaps_values [ok]");
+}
+
+fn main() {
+    Task::new()
+        .name("tests")
+        .stack_size(512)
+        .priority(TaskPriority(2))
+        .start(|_| {
+            barrier_releases_all_with_one_leader();
+            barrier_timeout_withdraws_arrival();
+            timeout_racing_last_arrival_completes_round();
+            rendezvous_swaps_values();
+            std::process::exit(0);
+        })
+        .unwrap();
+    FreeRtosUtils::start_scheduler();
+}
@@ -0,0 +1,160 @@
+//! `embassy-time` on the FreeRTOS tick, with the default 1 MHz `embassy-time` tick
+//! against the 1 kHz kernel tick. A plain binary like the other kernel tests.
+
+use freertos_next::*;
+use std::future::Future;
+use std::pin::pin;
+use std::sync::Arc;
+use std::sync::Mutex as StdMutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::task::{Context, Wake, Waker};
+
+struct Flag(AtomicBool);
+
+impl Wake for Flag {
+    fn wake(self: Arc<Self>) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Fills the timer command queue when woken.
+struct FillCommandQueue(Timer);
+
+impl Wake for FillCommandQueue {
+    fn wake(self: Arc<Self>) {
+        while self.0.start(Duration::zero()).is_ok() {}
+    }
+}
+
+fn now_follows_kernel_ticks() {
+    CurrentTask::delay(Duration::ms(5));
+    let kernel = Instant64::now().since_start().as_micros() as u64;
+    let embassy = embassy_time::Instant::now().as_micros();
+    // A tick may pass between the two reads.
+    assert!(
+        (kernel..=kernel + 1000).contains(&embassy),
+        "{kernel} us vs {embassy} us"
+    );
+    println!("now_follows_kernel_ticks [ok]");
+}
+
+fn timers_wake_in_order(spawner: &Spawner<4>) {
+    let woken = Arc::new(StdMutex::new(Vec::new()));
+    let start = Instant64::now();
+    for ms in [30, 10, 20] {
+        let woken = woken.clone();
+        spawner
+            .spawn(async move {
+                embassy_time::Timer::after_millis(ms).await;
+                woken.lock().unwrap().push((ms, start.elapsed()));
+            })
+            .unwrap();
+    }
+    CurrentTask::delay(Duration::ms(50));
+
+    let woken = woken.lock().unwrap();
+    let order: Vec<u64> = woken.iter().map(|(ms, _)| *ms).collect();
+    assert_eq!(order, [10, 20, 30]);
+    for (ms, elapsed) in woken.iter() {
+        assert!(
+            *elapsed >= Duration::ms(*ms as u32),
+            "{ms} ms timer after {elapsed:?}"
+        );
+        assert!(
+            *elapsed <= Duration::ms(*ms as u32 + 5),
+            "{ms} ms timer after {elapsed:?}"
+        );
+    }
+    println!("timers_wake_in_order [ok]");
+}
+
+fn timeout_cancels_wait() {
+    let queue = Queue::<u32>::new(1).unwrap();
+    let start = Instant64::now();
+    let result = block_on(embassy_time::with_timeout(
+        embassy_time::Duration::from_millis(10),
+        queue.receive_async(),
+    ));
+    assert!(result.is_err());
+    assert!(start.elapsed() >= Duration::ms(10));
+    println!("timeout_cancels_wait [ok]");
+}
+
+fn alarm_is_rearmed_after_full_command_queue() {
+    let woken = Arc::new(Flag(AtomicBool::new(false)));
+    let waker = Waker::from(woken.clone());
+    let mut cx = Context::from_waker(&waker);
+    let busy = Timer::new(Duration::ms(1000)).create(|_| ()).unwrap();
+
+    let scheduler = SuspendScheduler::new(());
+    let mut first = pin!(embassy_time::Timer::after_millis(10));
+    {
+        // The daemon task can't run, so its command queue fills up and the
+        // alarm for the first timer can't be set.
+        let _suspended = scheduler.lock();
+        while busy.start(Duration::zero()).is_ok() {}
+        assert!(first.as_mut().poll(&mut cx).is_pending());
+    }
+    CurrentTask::delay(Duration::ms(20));
+
+    // A later timer doesn't change the earliest one, but sets the missing alarm.
+    let mut second = pin!(embassy_time::Timer::after_millis(100));
+    assert!(second.as_mut().poll(&mut cx).is_pending());
+    CurrentTask::delay(Duration::ms(5));
+    assert!(woken.0.load(Ordering::SeqCst));
+    println!("alarm_is_rearmed_after_full_command_queue [ok]");
+}
+
+fn alarm_is_retried_by_the_daemon() {
+    let filler = Timer::new(Duration::ms(1000)).create(|_| ()).unwrap();
+    let filler = Waker::from(Arc::new(FillCommandQueue(filler)));
+    let woken = Arc::new(Flag(AtomicBool::new(false)));
+    let waker = Waker::from(woken.clone());
+    let mut first = pin!(embassy_time::Timer::after_millis(10));
+    let mut second = pin!(embassy_time::Timer::after_millis(20));
+    assert!(
+        first
+            .as_mut()
+            .poll(&mut Context::from_waker(&filler))
+            .is_pending()
+    );
+    assert!(
+        second
+            .as_mut()
+            .poll(&mut Context::from_waker(&waker))
+            .is_pending()
+    );
+
+    // The first timer fills the command queue when the alarm callback wakes it, so
+    // the callback can't set the alarm for the second. Nothing calls `schedule_wake`.
+    CurrentTask::delay(Duration::ms(40));
+    assert!(woken.0.load(Ordering::SeqCst));
+    println!("alarm_is_retried_by_the_daemon [ok]");
+}
+
+fn main() {
+    Task::new()
+        .name("tests")
+        .stack_size(512)
+        .priority(TaskPriority(2))
+        .start(|_| {
+            let executor = Executor::<4>::new();
+            let spawner = executor.spawner();
+            executor
+                .start(
+                    Task::new()
+                        .name("executor")
+                        .stack_size(512)
+                        .priority(TaskPriority(3)),
+                )
+                .unwrap();
+            now_follows_kernel_ticks();
+            timers_wake_in_order(&spawner);
+            timeout_cancels_wait();
+            alarm_is_rearmed_after_full_command_queue();
+            alarm_is_retried_by_the_daemon();
+            std::process::exit(0);
+        })
+        .unwrap();
+    FreeRtosUtils::start_scheduler();
+}
@@ -0,0 +1,127 @@
+//! Async executor on the host port, a plain binary like the other kernel tests.
+
+use freertos_next::*;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+/// A one-shot value that a FreeRTOS task hands to a future.
+#[derive(Default)]
+struct Signal {
+    state: Mutex<(Option<u32>, Option<Waker>)>,
+}
+
+impl Signal {
+    fn set(&self, value: u32) {
+        // Wake after unlocking, the woken executor task may preempt this one.
+        let waker = {
+            let mut state = self.state.lock().unwrap();
+            state.0 = Some(value);
+            state.1.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    fn wait(&self) -> Wait<'_> {
+        Wait(self)
+    }
+}
+
+struct Wait<'a>(&'a Signal);
+
+impl Future for Wait<'_> {
+    type Output = u32;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<u32> {
+        let mut state = self.0.state.lock().unwrap();
+        match state.0.take() {
+            Some(value) => Poll::Ready(value),
+            None => {
+                state.1 = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+fn spawn_from_future() {
+    static SUM: AtomicU32 = AtomicU32::new(0);
+
+    let signal = Arc::new(Signal::default());
+    let executor = Executor::<2>::new();
+    let spawner = executor.spawner();
+    let receiver = signal.clone();
+    executor
+        .spawn(async move {
+            loop {
+                let value = receiver.wait().await;
+                spawner
+                    .spawn(async move {
+                        SUM.fetch_add(value, Ordering::SeqCst);
+                    })
+                    .unwrap();
+            }
+        })
+        .unwrap();
+    executor
+        .start(
+            Task::new()
+                .name("executor")
+                .stack_size(512)
+                .priority(TaskPriority(3)),
+        )
+        .unwrap();
+
+    for value in 1..=3 {
+        CurrentTask::delay(Duration::ms(5));
+        signal.set(value);
+    }
+    CurrentTask::delay(Duration::ms(5));
+    assert_eq!(SUM.load(Ordering::SeqCst), 6);
+    println!("spawn_from_future [ok]");
+}
+
+fn spawn_fails_when_full() {
+    let executor = Executor::<1>::new();
+    executor.spawn(async {}).unwrap();
+    assert_eq!(executor.spawn(async {}), Err(FreeRtosError::OutOfMemory));
+    println!("spawn_fails_when_full [ok]");
+}
+
+fn block_on_waits_for_other_task() {
+    let signal = Arc::new(Signal::default());
+    let sender = signal.clone();
+    Task::new()
+        .name("sender")
+        .stack_size(128)
+        .priority(TaskPriority(3))
+        .start(move |_| {
+            CurrentTask::delay(Duration::ms(5));
+            sender.set(42);
+            loop {
+                CurrentTask::delay(Duration::infinite());
+            }
+        })
+        .unwrap();
+    assert_eq!(block_on(signal.wait()), 42);
+    println!("block_on_waits_for_other_task [ok]");
+}
+
+fn main() {
+    Task::new()
+        .name("tests")
+        .stack_size(512)
+        .priority(TaskPriority(2))
+        .start(|_| {
+            spawn_from_future();
+            spawn_fails_when_full();
+            block_on_waits_for_other_task();
+            std::process::exit(0);
+        })
+        .unwrap();
+    FreeRtosUtils::start_scheduler();
+}
@@ -0,0 +1,77 @@
+//! `os-trait` implementation on the host port, runs with the Linux example configuration.
+//!
+//! The scheduler owns the main thread, so this is a plain binary that exits when all
+//! checks are done. A failed check panics in a task, which aborts the process.
+
+use freertos_next::os_trait::{
+    DelayNs, NotifierInterface, NotifyWaiterInterface, OsInterface, TickInstant, TickTimeout,
+};
+use freertos_next::*;
+
+#[global_allocator]
+static GLOBAL: FreeRtosAllocator = FreeRtosAllocator;
+
+type OsDuration<OS> = freertos_next::os_trait::Duration<OS>;
+
+fn instant_measures_host_time() {
+    let mut start = FreeRtosInstant::now();
+    CurrentTask::delay(Duration::ms(20));
+    let elapsed = start.elapsed().as_micros();
+    assert!((19_000..100_000).contains(&elapsed), "elapsed {elapsed} us");
+
+    let mut timeout = TickTimeout::<FreeRtosInstant>::millis(10);
+    assert!(!timeout.timeout());
+    CurrentTask::delay(Duration::ms(15));
+    assert!(timeout.timeout());
+    println!("instant_measures_host_time [ok]");
+}
+
+fn delay_waits() {
+    let mut delay = FreeRTOS::<SemaphoreNotifier>::delay();
+    let start = Instant64::now();
+    delay.delay_ms(10);
+    delay.delay_us(500);
+    assert!(start.elapsed() >= Duration::ms(10));
+    println!("delay_waits [ok]");
+}
+
+fn notify<N: NotifyBuilder>(name: &str)
+where
+    N::Notifier: 'static,
+{
+    let (notifier, waiter) = FreeRTOS::<N>::notify();
+    let start = Instant64::now();
+    assert!(!waiter.wait(&OsDuration::<FreeRTOS<N>>::millis(10)));
+    assert!(start.elapsed() >= Duration::ms(10));
+
+    Task::new()
+        .name("notifier")
+        .stack_size(128)
+        .priority(TaskPriority(3))
+        .start(move |_| {
+            CurrentTask::delay(Duration::ms(5));
+            notifier.notify();
+            loop {
+                CurrentTask::delay(Duration::infinite());
+            }
+        })
+        .unwrap();
+    assert!(waiter.wait(&OsDuration::<FreeRTOS<N>>::millis(1000)));
+    println!("notify {name} [ok]");
+}
+
+fn main() {
+    Task::new()
+        .name("tests")
+        .stack_size(512)
+        .priority(TaskPriority(2))
+        .start(|_| {
+            instant_measures_host_time();
+            delay_waits();
+            notify::<SemaphoreNotifier>("semaphore");
+            notify::<TaskNotifier>("task");
+            std::process::exit(0);
+        })
+        .unwrap();
+    FreeRtosUtils::start_scheduler();
+}
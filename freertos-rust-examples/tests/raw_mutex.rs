@@ -0,0 +1,65 @@
+//! embassy-sync blocking mutexes on the host port, a plain binary like the other kernel tests.
+
+use core::cell::Cell;
+use embassy_sync::blocking_mutex::Mutex;
+use freertos_next::*;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+static COUNTER: Mutex<FreeRtosRecursiveRawMutex, Cell<u32>> = Mutex::new(Cell::new(0));
+
+fn nested_lock_does_not_deadlock() {
+    COUNTER.lock(|outer| {
+        COUNTER.lock(|inner| inner.set(inner.get() + 1));
+        outer.set(outer.get() + 1);
+    });
+    assert_eq!(COUNTER.lock(|c| c.get()), 2);
+    println!("nested_lock_does_not_deadlock [ok]");
+}
+
+fn other_task_waits_for_unlock() {
+    static SEEN: AtomicU32 = AtomicU32::new(0);
+
+    COUNTER.lock(|c| {
+        Task::new()
+            .name("locker")
+            .stack_size(128)
+            .priority(TaskPriority(3))
+            .start(|_| {
+                let value = COUNTER.lock(|c| c.get());
+                SEEN.store(value, Ordering::SeqCst);
+                loop {
+                    CurrentTask::delay(Duration::infinite());
+                }
+            })
+            .unwrap();
+        CurrentTask::delay(Duration::ms(5));
+        assert_eq!(SEEN.load(Ordering::SeqCst), 0);
+        c.set(10);
+    });
+    CurrentTask::delay(Duration::ms(5));
+    assert_eq!(SEEN.load(Ordering::SeqCst), 10);
+    println!("other_task_waits_for_unlock [ok]");
+}
+
+fn critical_section_flavor_nests() {
+    static FLAG: Mutex<FreeRtosCriticalRawMutex, Cell<bool>> = Mutex::new(Cell::new(false));
+
+    FLAG.lock(|outer| FLAG.lock(|inner| inner.set(!outer.get())));
+    assert!(FLAG.lock(|f| f.get()));
+    println!("critical_section_flavor_nests [ok]");
+}
+
+fn main() {
+    Task::new()
+        .name("tests")
+        .stack_size(512)
+        .priority(TaskPriority(2))
+        .start(|_| {
+            nested_lock_does_not_deadlock();
+            other_task_waits_for_unlock();
+            critical_section_flavor_nests();
+            std::process::exit(0);
+        })
+        .unwrap();
+    FreeRtosUtils::start_scheduler();
+}
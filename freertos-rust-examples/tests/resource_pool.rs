@@ -0,0 +1,81 @@
+//! `ResourcePool` on the host port, a plain binary like the other kernel tests.
+
+use freertos_next::*;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+static POOL: ResourcePool<u32, 2> = ResourcePool::new([10, 20]);
+
+fn acquire_and_release() {
+    assert_eq!(POOL.available(), 2);
+    {
+        let mut first = POOL.acquire(Duration::zero()).unwrap();
+        let second = POOL.acquire(Duration::zero()).unwrap();
+        assert_ne!(first.index(), second.index());
+        assert_eq!(*first + *second, 30);
+        *first += 1;
+        assert_eq!(POOL.available(), 0);
+    }
+    assert_eq!(POOL.available(), 2);
+    let sum: u32 = [
+        *POOL.acquire(Duration::zero()).unwrap(),
+        *POOL.acquire(Duration::zero()).unwrap(),
+    ]
+    .iter()
+    .sum();
+    assert_eq!(sum, 31);
+    println!("acquire_and_release [ok]");
+}
+
+fn exhausted_pool_times_out() {
+    let _first = POOL.acquire(Duration::zero()).unwrap();
+    let _second = POOL.acquire(Duration::zero()).unwrap();
+    let start = Instant64::now();
+    assert_eq!(
+        POOL.acquire(Duration::ms(10)).err(),
+        Some(FreeRtosError::Timeout)
+    );
+    assert!(start.elapsed() >= Duration::ms(10));
+    println!("exhausted_pool_times_out [ok]");
+}
+
+fn waiter_gets_released_resource() {
+    static GOT: AtomicU32 = AtomicU32::new(u32::MAX);
+
+    let first = POOL.acquire(Duration::zero()).unwrap();
+    let _second = POOL.acquire(Duration::zero()).unwrap();
+    let released = first.index() as u32;
+    Task::new()
+        .name("waiter")
+        .stack_size(128)
+        .priority(TaskPriority(3))
+        .start(|_| {
+            let resource = POOL.acquire(Duration::infinite()).unwrap();
+            GOT.store(resource.index() as u32, Ordering::SeqCst);
+            loop {
+                CurrentTask::delay(Duration::infinite());
+            }
+        })
+        .unwrap();
+    CurrentTask::delay(Duration::ms(5));
+    assert_eq!(GOT.load(Ordering::SeqCst), u32::MAX);
+
+    drop(first);
+    CurrentTask::delay(Duration::ms(5));
+    assert_eq!(GOT.load(Ordering::SeqCst), released);
+    println!("waiter_gets_released_resource [ok]");
+}
+
+fn main() {
+    Task::new()
+        .name("tests")
+        .stack_size(512)
+        .priority(TaskPriority(2))
+        .start(|_| {
+            acquire_and_release();
+            exhausted_pool_times_out();
+            waiter_gets_released_resource();
+            std::process::exit(0);
+        })
+        .unwrap();
+    FreeRtosUtils::start_scheduler();
+}
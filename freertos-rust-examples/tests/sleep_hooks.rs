@@ -0,0 +1,108 @@
+//! Tickless idle hooks, simulated because the POSIX port never sleeps.
+//!
+//! The kernel's critical sections aren't meant for the test harness's threads,
+//! so this is a plain binary as well.
+
+use freertos_next::*;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static LOG: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+fn take_log() -> Vec<String> {
+    std::mem::take(&mut *LOG.lock().unwrap())
+}
+
+struct Clock {
+    name: &'static str,
+    busy: AtomicBool,
+}
+
+impl Clock {
+    const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            busy: AtomicBool::new(false),
+        }
+    }
+}
+
+impl SleepHook for Clock {
+    fn allow_sleep(&self, _: FreeRtosTickType) -> bool {
+        !self.busy.load(Ordering::Relaxed)
+    }
+
+    fn pre_sleep(&self, ticks: FreeRtosTickType) {
+        LOG.lock()
+            .unwrap()
+            .push(format!("{} off {ticks}", self.name));
+    }
+
+    fn post_sleep(&self, ticks: FreeRtosTickType) {
+        LOG.lock()
+            .unwrap()
+            .push(format!("{} on {ticks}", self.name));
+    }
+}
+
+static UART: Clock = Clock::new("uart");
+static SPI: Clock = Clock::new("spi");
+
+fn hooks_run_in_order() {
+    SLEEP_HOOKS.register(&UART).unwrap();
+    SLEEP_HOOKS.register(&SPI).unwrap();
+
+    assert!(SLEEP_HOOKS.simulate(7));
+    assert_eq!(
+        take_log(),
+        ["uart off 7", "spi off 7", "spi on 7", "uart on 7"]
+    );
+    println!("hooks_run_in_order [ok]");
+}
+
+fn busy_hook_vetoes() {
+    SPI.busy.store(true, Ordering::Relaxed);
+    assert!(!SLEEP_HOOKS.simulate(7));
+    assert_eq!(freertos_rs_pre_suppress_ticks_and_sleep(7), 0);
+    assert!(take_log().is_empty());
+
+    SPI.busy.store(false, Ordering::Relaxed);
+    assert_eq!(freertos_rs_pre_suppress_ticks_and_sleep(7), 7);
+    println!("busy_hook_vetoes [ok]");
+}
+
+fn unregister_keeps_order() {
+    static ADC: Clock = Clock::new("adc");
+    SLEEP_HOOKS.register(&ADC).unwrap();
+    assert!(SLEEP_HOOKS.unregister(&UART));
+    assert!(!SLEEP_HOOKS.unregister(&UART));
+
+    freertos_rs_pre_sleep_processing(3);
+    freertos_rs_post_sleep_processing(3);
+    assert_eq!(
+        take_log(),
+        ["spi off 3", "adc off 3", "adc on 3", "spi on 3"]
+    );
+    println!("unregister_keeps_order [ok]");
+}
+
+fn registry_is_bounded() {
+    static EXTRA: [Clock; SLEEP_HOOK_SLOTS] = [const { Clock::new("extra") }; SLEEP_HOOK_SLOTS];
+    let registered = EXTRA
+        .iter()
+        .take_while(|hook| SLEEP_HOOKS.register(*hook).is_ok())
+        .count();
+    assert_eq!(registered, SLEEP_HOOK_SLOTS - 2);
+    assert_eq!(
+        SLEEP_HOOKS.register(&EXTRA[SLEEP_HOOK_SLOTS - 1]),
+        Err(FreeRtosError::OutOfMemory)
+    );
+    println!("registry_is_bounded [ok]");
+}
+
+fn main() {
+    hooks_run_in_order();
+    busy_hook_vetoes();
+    unregister_keeps_order();
+    registry_is_bounded();
+}
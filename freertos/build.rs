@@ -1,4 +1,3 @@
-use std::collections::HashMap;
 use std::env;
 use std::path::PathBuf;
 
@@ -7,12 +6,28 @@ fn main() {
     println!("cargo:rerun-if-changed=build.rs");
 
     println!("cargo:rustc-check-cfg=cfg(cortex_m)");
+    println!("cargo:rustc-check-cfg=cfg(hosted)");
+    println!("cargo:rustc-check-cfg=cfg(has_dwt)");
     let target = env::var("TARGET").unwrap();
     if target.starts_with("thumbv") {
         println!("cargo:rustc-cfg=cortex_m");
         println!("cargo:rustc-cfg=feature=\"cpu-clock\"");
         println!("cargo:DEF___IS_CORTEX_M=1");
     }
+    // ARMv6-M and ARMv8-M Baseline have no cycle counter.
+    if ["thumbv7m", "thumbv7em", "thumbv8m.main"]
+        .iter()
+        .any(|arch| target.starts_with(arch))
+    {
+        println!("cargo:rustc-cfg=has_dwt");
+    }
+
+    // The POSIX and Windows simulator ports.
+    let target_os = env::var("CARGO_CFG_TARGET_OS").unwrap_or_default();
+    if matches!(target_os.as_str(), "linux" | "macos" | "windows") {
+        println!("cargo:rustc-cfg=hosted");
+        println!("cargo:DEF___IS_HOSTED=1");
+    }
 
     let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
     println!("cargo:CRATE_DIR={}", manifest_dir.to_str().unwrap());
@@ -25,7 +40,8 @@ fn main() {
         manifest_dir.join("src/freertos").to_str().unwrap()
     );
 
-    let feature_define_map = HashMap::from([
+    // A feature can enable several defines.
+    let feature_define_map = [
         ("delete-task", "INCLUDE_vTaskDelete"),
         ("delay-until", "INCLUDE_vTaskDelayUntil"),
         ("stack-high-water", "INCLUDE_uxTaskGetStackHighWaterMark"),
@@ -34,7 +50,10 @@ fn main() {
         ("recursive-mutex", "configUSE_RECURSIVE_MUTEXES"),
         ("counting-semaphore", "configUSE_COUNTING_SEMAPHORES"),
         ("trace-facility", "configUSE_TRACE_FACILITY"),
-    ]);
+        ("static-allocation", "configSUPPORT_STATIC_ALLOCATION"),
+        ("task-priority", "INCLUDE_vTaskPrioritySet"),
+        ("task-priority", "INCLUDE_uxTaskPriorityGet"),
+    ];
 
     for (ft, def) in feature_define_map.iter() {
         if check_feature(ft) {
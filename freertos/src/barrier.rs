@@ -0,0 +1,230 @@
+use crate::base::*;
+use crate::base_type::v1::*;
+use crate::critical::*;
+use crate::event_group::*;
+use crate::queue::SendError;
+use crate::units::*;
+use crate::wait_queue::*;
+
+/// Hands out one event bit per arriving task and tracks the round they belong to.
+struct Arrivals {
+    /// Bits taken in the current round.
+    taken: FreeRtosEventBitsType,
+    /// Incremented when the last participant of a round arrives.
+    round: u32,
+    /// Set to `round + 1` once the kernel cleared the bits of `round`.
+    released: u32,
+}
+
+#[derive(Copy, Clone)]
+struct Ticket {
+    bit: FreeRtosEventBitsType,
+    round: u32,
+    leader: bool,
+}
+
+/// The arrival bookkeeping of [`Barrier`] and [`Rendezvous`].
+struct Rounds {
+    all: FreeRtosEventBitsType,
+    arrivals: UnsafeCell<Arrivals>,
+    /// Tasks that timed out after their round completed, woken when it is released.
+    late: WaitQueue,
+}
+
+impl Rounds {
+    fn new(participants: u32) -> Result<Self, FreeRtosError> {
+        if participants == 0 || participants > event_group_max_bits() {
+            return Err(FreeRtosError::InvalidParticipantCount);
+        }
+        Ok(Self {
+            all: FreeRtosEventBitsType::MAX >> (FreeRtosEventBitsType::BITS - participants),
+            arrivals: UnsafeCell::new(Arrivals {
+                taken: 0,
+                round: 0,
+                released: 0,
+            }),
+            late: WaitQueue::new(),
+        })
+    }
+
+    fn arrive(&self) -> Ticket {
+        let _lock = CriticalRegion::enter();
+        let arrivals = unsafe { &mut *self.arrivals.get() };
+        let free = self.all & !arrivals.taken;
+        let bit = free & free.wrapping_neg();
+        let ticket = Ticket {
+            bit,
+            round: arrivals.round,
+            leader: arrivals.taken | bit == self.all,
+        };
+        if ticket.leader {
+            arrivals.taken = 0;
+            arrivals.round = arrivals.round.wrapping_add(1);
+        } else {
+            arrivals.taken |= bit;
+        }
+        ticket
+    }
+
+    /// Set the ticket's bit in `bits` and wait for the round, `shift` selects the phase.
+    ///
+    /// Returns `false` if the wait timed out and the arrival was withdrawn, `withdraw`
+    /// runs inside the critical region that withdraws it. Once the leader arrived the
+    /// round can no longer be left, whoever sets the last bit completes it with ours.
+    fn sync<D: DurationTicks>(
+        &self,
+        group: &EventGroup,
+        ticket: Ticket,
+        shift: u32,
+        max_wait: D,
+        withdraw: impl FnOnce(),
+    ) -> bool {
+        let all = self.all << shift;
+        let bit = ticket.bit << shift;
+        let released = ticket.round.wrapping_add(1);
+        if group.sync(bit, all, max_wait) & all == all {
+            // Recorded before this task can arrive for the next round, which can't
+            // be completed without it, so `released` only moves forward.
+            {
+                let _lock = CriticalRegion::enter();
+                unsafe { (*self.arrivals.get()).released = released };
+            }
+            self.late.notify_all();
+            return true;
+        }
+
+        {
+            let _lock = CriticalRegion::enter();
+            let arrivals = unsafe { &mut *self.arrivals.get() };
+            if arrivals.round == ticket.round {
+                arrivals.taken &= !ticket.bit;
+                group.clear_bits(bit);
+                withdraw();
+                return false;
+            }
+        }
+
+        // Our bit is still set, or was already cleared with the others. Setting it
+        // again would count for the next round, and the bit may already be reused
+        // by it, so wait until the round is recorded as released. The round is
+        // complete, so this doesn't take long and can't time out.
+        while !self.late.wait_unless(Duration::infinite(), || unsafe {
+            (*self.arrivals.get()).released == released
+        }) {}
+        true
+    }
+}
+
+/// Result of [`Barrier::wait`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BarrierWaitResult(bool);
+
+impl BarrierWaitResult {
+    /// `true` for exactly one task of every round, the last one to arrive.
+    pub fn is_leader(&self) -> bool {
+        self.0
+    }
+}
+
+/// A reusable barrier for a fixed number of tasks, built on [`EventGroup::sync`].
+///
+/// Every task that calls [`wait`](Barrier::wait) gets a free event bit, so the tasks
+/// don't need to be assigned bits up front. The barrier is meant to be shared by
+/// exactly `n` tasks, a task that arrives for the next round while the previous
+/// one is still being released would take part in the wrong round.
+///
+/// ```ignore
+/// let barrier = Arc::new(Barrier::new(3)?);
+/// // in each of the three tasks:
+/// if barrier.wait(Duration::ms(100))?.is_leader() {
+///     // runs once per round
+/// }
+/// ```
+pub struct Barrier {
+    group: EventGroup,
+    rounds: Rounds,
+}
+
+unsafe impl Send for Barrier {}
+unsafe impl Sync for Barrier {}
+
+impl Barrier {
+    /// Create a barrier for `n` tasks. Fails with [`FreeRtosError::InvalidParticipantCount`]
+    /// if `n` is 0 or more than [`event_group_max_bits`].
+    pub fn new(n: u32) -> Result<Self, FreeRtosError> {
+        Ok(Self {
+            rounds: Rounds::new(n)?,
+            group: EventGroup::new()?,
+        })
+    }
+
+    /// Block until all `n` tasks have called `wait`.
+    ///
+    /// On [`FreeRtosError::Timeout`] the task no longer counts as arrived.
+    pub fn wait<D: DurationTicks>(&self, max_wait: D) -> Result<BarrierWaitResult, FreeRtosError> {
+        let ticket = self.rounds.arrive();
+        if self.rounds.sync(&self.group, ticket, 0, max_wait, || ()) {
+            Ok(BarrierWaitResult(ticket.leader))
+        } else {
+            Err(FreeRtosError::Timeout)
+        }
+    }
+}
+
+/// A meeting point where two tasks swap values.
+///
+/// The first task to call [`exchange`](Rendezvous::exchange) blocks until the second
+/// one arrives, then each gets the value of the other. It is meant to be shared by
+/// exactly two tasks.
+///
+/// ```ignore
+/// // producer
+/// let empty = rendezvous.exchange(full_buffer, Duration::infinite())?;
+/// // consumer
+/// let full = rendezvous.exchange(empty_buffer, Duration::infinite())?;
+/// ```
+pub struct Rendezvous<T> {
+    group: EventGroup,
+    rounds: Rounds,
+    slots: [UnsafeCell<Option<T>>; 2],
+}
+
+unsafe impl<T: Send> Send for Rendezvous<T> {}
+unsafe impl<T: Send> Sync for Rendezvous<T> {}
+
+impl<T> Rendezvous<T> {
+    pub fn new() -> Result<Self, FreeRtosError> {
+        Ok(Self {
+            rounds: Rounds::new(2)?,
+            group: EventGroup::new()?,
+            slots: [UnsafeCell::new(None), UnsafeCell::new(None)],
+        })
+    }
+
+    /// Give `value` to the other task and return its value.
+    ///
+    /// Fails with [`FreeRtosError::Timeout`] if the other task didn't arrive in time,
+    /// the value is returned in the error.
+    pub fn exchange<D: DurationTicks>(&self, value: T, max_wait: D) -> Result<T, SendError<T>> {
+        let ticket = self.rounds.arrive();
+        let mine = ticket.bit.trailing_zeros() as usize;
+        unsafe { *self.slots[mine].get() = Some(value) };
+
+        let mut value = None;
+        // The slot has to be emptied before the bit can be handed to another task.
+        let withdraw = || value = unsafe { (*self.slots[mine].get()).take() };
+        if !self.rounds.sync(&self.group, ticket, 0, max_wait, withdraw) {
+            return Err(SendError::new(
+                FreeRtosError::Timeout,
+                value.expect("rendezvous slot is empty"),
+            ));
+        }
+
+        let theirs = unsafe { (*self.slots[mine ^ 1].get()).take() };
+        // Neither task may leave, and arrive for the next exchange, before
+        // both have taken their value.
+        self.rounds
+            .sync(&self.group, ticket, 2, Duration::infinite(), || ());
+        Ok(theirs.expect("rendezvous slot is empty"))
+    }
+}
@@ -1,20 +1,73 @@
 use core::ffi::c_void;
+use core::fmt;
 
 /// Basic error type for the library.
+///
+/// New variants may be added in minor releases.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
 pub enum FreeRtosError {
     OutOfMemory,
     QueueSendTimeout,
     QueueReceiveTimeout,
     MutexTimeout,
+    /// The mutex was not released in time, it is still held by the named task.
+    MutexTimeoutHeldBy(TaskName),
+    /// A task panicked while holding the mutex.
+    MutexPoisoned,
     Timeout,
     QueueFull,
     StringConversionError,
     TaskNotFound,
     InvalidQueueSize,
+    /// The number of participants doesn't fit in an event group.
+    InvalidParticipantCount,
     ProcessorHasShutDown,
 }
 
+/// Maximum number of bytes kept by [`TaskName`].
+pub const TASK_NAME_CAPACITY: usize = 16;
+
+/// A copy of a task name that doesn't need the heap.
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub struct TaskName {
+    buf: [u8; TASK_NAME_CAPACITY],
+    len: u8,
+}
+
+impl TaskName {
+    /// Copy `name`, truncating it to [`TASK_NAME_CAPACITY`] bytes.
+    pub fn new(name: &str) -> Self {
+        let mut len = name.len().min(TASK_NAME_CAPACITY);
+        while !name.is_char_boundary(len) {
+            len -= 1;
+        }
+
+        let mut buf = [0; TASK_NAME_CAPACITY];
+        buf[..len].copy_from_slice(&name.as_bytes()[..len]);
+        Self {
+            buf,
+            len: len as u8,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.buf[..self.len as usize]).unwrap_or_default()
+    }
+}
+
+impl fmt::Debug for TaskName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.as_str())
+    }
+}
+
+impl fmt::Display for TaskName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 pub type FreeRtosVoidPtr = *const c_void;
 pub type FreeRtosMutVoidPtr = *mut c_void;
 pub type FreeRtosCharPtr = *const u8;
@@ -0,0 +1,159 @@
+use crate::base::*;
+use crate::base_type::v1::*;
+use crate::mutex::*;
+#[cfg(feature = "poison")]
+use crate::poison::*;
+use crate::shim::*;
+use crate::task::*;
+use crate::units::*;
+use core::mem::ManuallyDrop;
+
+/// A mutex using the immediate priority ceiling protocol.
+///
+/// The locking task is raised to the ceiling priority as soon as it takes the lock
+/// and goes back to its base priority when the guard is dropped. The ceiling must be
+/// at least the priority of every task that uses the mutex, which is checked in debug builds.
+/// Nested ceiling mutexes have to be locked in order of increasing ceiling.
+///
+/// Requires the `task-priority` feature.
+pub struct CeilingMutex<T: ?Sized> {
+    ceiling: TaskPriority,
+    inner: Mutex<T>,
+}
+
+impl<T> CeilingMutex<T> {
+    /// Create a new mutex with the given ceiling priority and inner value
+    pub fn new(value: T, ceiling: TaskPriority) -> Result<Self, FreeRtosError> {
+        Ok(Self {
+            ceiling,
+            inner: Mutex::new(value)?,
+        })
+    }
+
+    /// Consume the mutex and return its inner value
+    pub fn into_inner(self) -> T {
+        self.inner.into_inner()
+    }
+}
+
+impl<T: ?Sized> CeilingMutex<T> {
+    pub fn ceiling(&self) -> TaskPriority {
+        self.ceiling
+    }
+}
+
+impl<T> CeilingMutex<T> {
+    /// Raise the current task to the ceiling and try to obtain the lock.
+    pub fn lock<D: DurationTicks>(
+        &self,
+        max_wait: D,
+    ) -> Result<CeilingMutexGuard<'_, T>, FreeRtosError> {
+        let (task, restore) = self.raise();
+        match self.inner.lock(max_wait) {
+            Ok(guard) => Ok(CeilingMutexGuard {
+                guard: ManuallyDrop::new(guard),
+                task,
+                restore,
+            }),
+            Err(e) => {
+                if let Some(base) = restore {
+                    task.set_priority(base);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Like [`lock`](CeilingMutex::lock), but returns the guard of a poisoned lock
+    /// inside the error, see [`MutexImpl::lock_poisoned`].
+    #[cfg(feature = "poison")]
+    pub fn lock_poisoned<D: DurationTicks>(
+        &self,
+        max_wait: D,
+    ) -> LockResult<CeilingMutexGuard<'_, T>> {
+        let (task, restore) = self.raise();
+        let mut taken = false;
+        let result = map_lock_result(self.inner.lock_poisoned(max_wait), |guard| {
+            taken = true;
+            CeilingMutexGuard {
+                guard: ManuallyDrop::new(guard),
+                task: task.clone(),
+                restore,
+            }
+        });
+        if !taken && let Some(base) = restore {
+            task.set_priority(base);
+        }
+        result
+    }
+
+    /// Raise the current task to the ceiling, returns the priority to restore.
+    fn raise(&self) -> (Task, Option<TaskPriority>) {
+        let task = unsafe { Task::from_raw_handle(freertos_rs_get_current_task()) };
+        let base = task.base_priority();
+        debug_assert!(
+            base <= self.ceiling,
+            "task {} with priority {} locks a mutex with ceiling {}",
+            task.name(),
+            base.0,
+            self.ceiling.0
+        );
+
+        let restore = if base < self.ceiling {
+            task.set_priority(self.ceiling);
+            Some(base)
+        } else {
+            None
+        };
+        (task, restore)
+    }
+
+    /// Whether a task panicked while holding the lock. Needs the `poison` feature.
+    pub fn is_poisoned(&self) -> bool {
+        self.inner.is_poisoned()
+    }
+
+    /// Clear the poisoned state, after the data has been checked or repaired.
+    pub fn clear_poison(&self) {
+        self.inner.clear_poison()
+    }
+
+    /// Get mutable reference to inner value.
+    ///
+    /// This method does not lock the mutex because mutable reference guarantees exclusive access.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.inner.get_mut()
+    }
+}
+
+/// Holds the lock and the raised priority of a [`CeilingMutex`] until dropped.
+pub struct CeilingMutexGuard<'a, T: ?Sized + 'a> {
+    guard: ManuallyDrop<MutexGuard<'a, T, MutexNormal>>,
+    task: Task,
+    restore: Option<TaskPriority>,
+}
+
+impl<T: ?Sized> Deref for CeilingMutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T: ?Sized> DerefMut for CeilingMutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl<T: ?Sized> Drop for CeilingMutexGuard<'_, T> {
+    fn drop(&mut self) {
+        // Unlock before lowering the priority, so no task that uses the mutex
+        // can preempt us while we still hold it.
+        unsafe { ManuallyDrop::drop(&mut self.guard) };
+        if let Some(base) = self.restore {
+            self.task.set_priority(base);
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use crate::base_type::v1::*;
+use crate::instant::*;
+use crate::mutex::*;
+use crate::units::*;
+use crate::wait_queue::*;
+
+/// A condition variable, used together with a [`Mutex`] to block until a condition holds.
+///
+/// Waiting tasks are kept in a list of stack allocated nodes and woken in FIFO order
+/// with task notifications, so neither creating a `Condvar` nor waiting on it allocates.
+/// It uses the task notification index reserved by the crate, the default
+/// notification of a task stays free for the application.
+///
+/// ```ignore
+/// static READY: Condvar = Condvar::new();
+///
+/// let mut guard = mutex.lock(Duration::infinite())?;
+/// guard = READY.wait_while(guard, |queue| queue.is_empty());
+/// ```
+///
+/// The notify methods must not be called from an interrupt.
+pub struct Condvar {
+    waiters: WaitQueue,
+}
+
+/// Returned by the timed waits of a [`Condvar`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct WaitTimeoutResult(bool);
+
+impl WaitTimeoutResult {
+    /// Whether the wait ended because the timeout elapsed.
+    pub fn timed_out(&self) -> bool {
+        self.0
+    }
+}
+
+impl Default for Condvar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Condvar {
+    pub const fn new() -> Self {
+        Self {
+            waiters: WaitQueue::new(),
+        }
+    }
+
+    /// Release the lock and block until notified, then lock again.
+    ///
+    /// Spurious wakeups can't happen, but the condition may have changed again by the
+    /// time the lock is taken back, so prefer [`wait_while`](Condvar::wait_while).
+    pub fn wait<'a, T: ?Sized>(
+        &self,
+        guard: MutexGuard<'a, T, MutexNormal>,
+    ) -> MutexGuard<'a, T, MutexNormal> {
+        self.wait_timeout(guard, Duration::infinite()).0
+    }
+
+    /// Like [`wait`](Condvar::wait), but gives up after `max_wait`.
+    ///
+    /// The lock is held again when this returns, also on a timeout.
+    pub fn wait_timeout<'a, T: ?Sized, D: DurationTicks>(
+        &self,
+        guard: MutexGuard<'a, T, MutexNormal>,
+        max_wait: D,
+    ) -> (MutexGuard<'a, T, MutexNormal>, WaitTimeoutResult) {
+        // The task is queued before the mutex is given back, so a notification sent
+        // right after the release is not lost.
+        let notified = self.waiters.wait(max_wait, || guard.release());
+        guard.reacquire();
+        (guard, WaitTimeoutResult(!notified))
+    }
+
+    /// Block as long as `condition` returns `true`.
+    pub fn wait_while<'a, T: ?Sized, F>(
+        &self,
+        mut guard: MutexGuard<'a, T, MutexNormal>,
+        mut condition: F,
+    ) -> MutexGuard<'a, T, MutexNormal>
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        while condition(&mut *guard) {
+            guard = self.wait(guard);
+        }
+        guard
+    }
+
+    /// Block as long as `condition` returns `true`, but at most for `max_wait` in total.
+    pub fn wait_timeout_while<'a, T: ?Sized, D: DurationTicks, F>(
+        &self,
+        mut guard: MutexGuard<'a, T, MutexNormal>,
+        max_wait: D,
+        mut condition: F,
+    ) -> (MutexGuard<'a, T, MutexNormal>, WaitTimeoutResult)
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        let budget = Deadline::after(max_wait);
+        while condition(&mut *guard) {
+            let (g, res) = self.wait_timeout(guard, budget);
+            guard = g;
+            if res.timed_out() {
+                let timed_out = condition(&mut *guard);
+                return (guard, WaitTimeoutResult(timed_out));
+            }
+        }
+        (guard, WaitTimeoutResult(false))
+    }
+
+    /// Wake the task that has been waiting the longest.
+    pub fn notify_one(&self) {
+        self.waiters.notify_one();
+    }
+
+    /// Wake all waiting tasks.
+    pub fn notify_all(&self) {
+        self.waiters.notify_all();
+    }
+}
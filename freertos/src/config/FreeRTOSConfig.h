@@ -26,6 +26,57 @@ extern void assert_callback(uint32_t ulLine, const char *const pcFileName);
 #    define configTICK_RATE_HZ ((TickType_t)1000)  // 1000=1ms per tick, 100=10ms per tick
 #endif
 
+// Let the kernel allocate the idle and timer task of static-only builds,
+// so the application does not have to provide vApplicationGet*TaskMemory.
+#if (configSUPPORT_STATIC_ALLOCATION == 1) && !defined(configKERNEL_PROVIDED_STATIC_MEMORY)
+#    define configKERNEL_PROVIDED_STATIC_MEMORY 1
+#endif
+
+// Needed to defer work from ISRs to the timer daemon task.
+#if (configUSE_TIMERS == 1) && !defined(INCLUDE_xTimerPendFunctionCall)
+#    define INCLUDE_xTimerPendFunctionCall 1
+#endif
+
+// Run-time stats from the DWT cycle counter, see `run_time_stats_dwt` of freertos-build.
+#if defined(__RUN_TIME_STATS_DWT) && (__RUN_TIME_STATS_DWT == 1)
+extern void freertos_rs_dwt_configure_run_time_counter(void);
+extern uint32_t freertos_rs_dwt_run_time_counter(void);
+#    define portCONFIGURE_TIMER_FOR_RUN_TIME_STATS() freertos_rs_dwt_configure_run_time_counter()
+#    define portGET_RUN_TIME_COUNTER_VALUE()         freertos_rs_dwt_run_time_counter()
+#endif
+
+// Tickless idle calls the `SLEEP_HOOKS` of the crate, see `tickless_idle` of freertos-build.
+// `TickType_t` isn't defined yet, so the hooks are declared where the macros expand.
+// The shim checks that it is 32 bits wide like the tick count on the Rust side.
+#if defined(configUSE_TICKLESS_IDLE) && (configUSE_TICKLESS_IDLE != 0)
+#    ifndef configPRE_SUPPRESS_TICKS_AND_SLEEP_PROCESSING
+#        define configPRE_SUPPRESS_TICKS_AND_SLEEP_PROCESSING(x)                           \
+            do {                                                                        \
+                extern TickType_t freertos_rs_pre_suppress_ticks_and_sleep(TickType_t); \
+                (x) = freertos_rs_pre_suppress_ticks_and_sleep(x);                      \
+            } while (0)
+#    endif
+#    ifndef configPRE_SLEEP_PROCESSING
+#        define configPRE_SLEEP_PROCESSING(x)                                \
+            do {                                                          \
+                extern void freertos_rs_pre_sleep_processing(TickType_t); \
+                freertos_rs_pre_sleep_processing(x);                      \
+            } while (0)
+#    endif
+#    ifndef configPOST_SLEEP_PROCESSING
+#        define configPOST_SLEEP_PROCESSING(x)                                \
+            do {                                                           \
+                extern void freertos_rs_post_sleep_processing(TickType_t); \
+                freertos_rs_post_sleep_processing(x);                      \
+            } while (0)
+#    endif
+#endif
+
+// The last notification index is reserved for the synchronization primitives of the crate.
+#ifndef configTASK_NOTIFICATION_ARRAY_ENTRIES
+#    define configTASK_NOTIFICATION_ARRAY_ENTRIES 2
+#endif
+
 #define configUSE_IDLE_HOOK                  0
 #define configUSE_TICK_HOOK                  0
 #define configUSE_16_BIT_TICKS               0
@@ -39,10 +90,9 @@ extern void assert_callback(uint32_t ulLine, const char *const pcFileName);
 #define configMAX_CO_ROUTINE_PRIORITIES (2)
 
 #define INCLUDE_vTaskDelay                1
-#define INCLUDE_vTaskPrioritySet          0
-#define INCLUDE_uxTaskPriorityGet         0
 #define INCLUDE_eTaskGetState             0
 #define INCLUDE_xTaskGetCurrentTaskHandle 1
+#define INCLUDE_xSemaphoreGetMutexHolder  1
 #define configUSE_TASK_NOTIFICATIONS      1
 
 #endif
@@ -1,6 +1,8 @@
 use crate::base::*;
+use crate::instant::*;
 #[cfg(feature = "delay-until")]
 use crate::shim::*;
+#[cfg(feature = "delay-until")]
 use crate::task::*;
 use crate::units::*;
 
@@ -11,6 +13,23 @@ pub struct TaskDelay {
     last_wake_time: FreeRtosTickType,
 }
 
+/// Outcome of [`TaskDelay::delay_until`].
+#[cfg(feature = "delay-until")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DelayResult {
+    /// The task blocked until the wake time.
+    OnTime,
+    /// The wake time had already passed by this many ticks, the task didn't block.
+    Missed(FreeRtosTickType),
+}
+
+#[cfg(feature = "delay-until")]
+impl DelayResult {
+    pub fn is_missed(&self) -> bool {
+        matches!(self, DelayResult::Missed(_))
+    }
+}
+
 #[cfg(feature = "delay-until")]
 impl TaskDelay {
     /// Create a new helper, marking the current time as the start of the
@@ -23,13 +42,131 @@ impl TaskDelay {
 
     /// Delay the execution of the current task by the given duration,
     /// minus the time spent in this task since the last delay.
-    pub fn delay_until<D: DurationTicks>(&mut self, delay: D) {
-        unsafe {
-            freertos_rs_vTaskDelayUntil(
+    ///
+    /// If that time has already passed the task doesn't block and the next delay
+    /// still counts from the missed wake time, so a late loop catches up.
+    pub fn delay_until<D: DurationTicks>(&mut self, delay: D) -> DelayResult {
+        let blocked = unsafe {
+            freertos_rs_xTaskDelayUntil(
                 &mut self.last_wake_time as *mut FreeRtosTickType,
                 delay.to_ticks(),
-            );
+            )
+        };
+        if blocked != 0 {
+            DelayResult::OnTime
+        } else {
+            let late = FreeRtosUtils::get_tick_count().wrapping_sub(self.last_wake_time);
+            DelayResult::Missed(late)
+        }
+    }
+
+    /// The wake time of the last delay.
+    pub fn last_wake_time(&self) -> Instant {
+        Instant::from_ticks(self.last_wake_time)
+    }
+}
+
+/// What [`PeriodicTask`] does after a loop overran its period.
+#[cfg(feature = "delay-until")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum MissedPeriodPolicy {
+    /// Run the missed periods back to back until the loop is on schedule again.
+    #[default]
+    CatchUp,
+    /// Drop the missed periods and continue with the next one that is still ahead.
+    Skip,
+}
+
+/// Timing statistics of a [`PeriodicTask`].
+#[cfg(feature = "delay-until")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct PeriodicStats {
+    /// Loops that have waited for their period.
+    pub loops: u32,
+    /// Loops that took longer than their period.
+    pub overruns: u32,
+    /// Periods dropped by [`MissedPeriodPolicy::Skip`].
+    pub skipped: u32,
+    /// Longest time from a wake-up to the next wait.
+    pub worst_execution_ticks: FreeRtosTickType,
+    /// Latest wake-up after an on-time wait.
+    pub max_jitter_ticks: FreeRtosTickType,
+}
+
+/// A fixed-rate loop that keeps timing statistics.
+///
+/// ```ignore
+/// let mut period = PeriodicTask::new(Duration::ms(10));
+/// period.set_policy(MissedPeriodPolicy::Skip);
+/// loop {
+///     control_step();
+///     if period.wait().is_missed() {
+///         warn!("overrun, {:?}", period.stats());
+///     }
+/// }
+/// ```
+#[cfg(feature = "delay-until")]
+pub struct PeriodicTask {
+    delay: TaskDelay,
+    period_ticks: FreeRtosTickType,
+    policy: MissedPeriodPolicy,
+    woken: Instant,
+    stats: PeriodicStats,
+}
+
+#[cfg(feature = "delay-until")]
+impl PeriodicTask {
+    /// The first period starts now.
+    pub fn new<D: DurationTicks>(period: D) -> Self {
+        let delay = TaskDelay::new();
+        Self {
+            woken: delay.last_wake_time(),
+            delay,
+            period_ticks: period.to_ticks(),
+            policy: MissedPeriodPolicy::default(),
+            stats: PeriodicStats::default(),
+        }
+    }
+
+    pub fn set_policy(&mut self, policy: MissedPeriodPolicy) {
+        self.policy = policy;
+    }
+
+    /// Wait for the start of the next period.
+    pub fn wait(&mut self) -> DelayResult {
+        let execution = self.woken.elapsed().to_ticks();
+        self.stats.worst_execution_ticks = self.stats.worst_execution_ticks.max(execution);
+        self.stats.loops = self.stats.loops.wrapping_add(1);
+
+        let result = self.delay.delay_until(Duration::ticks(self.period_ticks));
+        match result {
+            DelayResult::OnTime => {
+                let jitter = self.delay.last_wake_time().elapsed().to_ticks();
+                self.stats.max_jitter_ticks = self.stats.max_jitter_ticks.max(jitter);
+            }
+            DelayResult::Missed(late) => {
+                self.stats.overruns = self.stats.overruns.wrapping_add(1);
+                if self.policy == MissedPeriodPolicy::Skip {
+                    // Moves the next wake time past now.
+                    let skipped = late / self.period_ticks.max(1);
+                    self.delay.last_wake_time = self
+                        .delay
+                        .last_wake_time
+                        .wrapping_add(skipped.wrapping_mul(self.period_ticks));
+                    self.stats.skipped = self.stats.skipped.wrapping_add(skipped);
+                }
+            }
         }
+        self.woken = Instant::now();
+        result
+    }
+
+    pub fn stats(&self) -> PeriodicStats {
+        self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = PeriodicStats::default();
     }
 }
 
@@ -39,28 +176,26 @@ impl TaskDelay {
 /// The method `should_run` will return true once 30 seconds or more has elapsed
 /// and it will then reset the timer for that period.
 pub struct TaskDelayPeriodic {
-    last_wake_time: FreeRtosTickType,
+    last_wake_time: Instant,
     period_ticks: FreeRtosTickType,
 }
 
 impl TaskDelayPeriodic {
     /// Create a new timer with the set period.
     pub fn new<D: DurationTicks>(period: D) -> TaskDelayPeriodic {
-        let l = FreeRtosUtils::get_tick_count();
-
         TaskDelayPeriodic {
-            last_wake_time: l,
+            last_wake_time: Instant::now(),
             period_ticks: period.to_ticks(),
         }
     }
 
     /// Has the set period passed? If it has, resets the internal timer.
     pub fn should_run(&mut self) -> bool {
-        let c = FreeRtosUtils::get_tick_count();
-        if (c - self.last_wake_time) < (self.period_ticks) {
+        let now = Instant::now();
+        if now.duration_since(self.last_wake_time).to_ticks() < self.period_ticks {
             false
         } else {
-            self.last_wake_time = c;
+            self.last_wake_time = now;
             true
         }
     }
@@ -72,6 +207,6 @@ impl TaskDelayPeriodic {
 
     /// Reset the internal timer to zero.
     pub fn reset(&mut self) {
-        self.last_wake_time = FreeRtosUtils::get_tick_count();
+        self.last_wake_time = Instant::now();
     }
 }
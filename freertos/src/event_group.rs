@@ -1,24 +1,112 @@
 use crate::base::*;
+use crate::base_type::v1::*;
+#[cfg(feature = "trace-facility")]
+use crate::isr::*;
 use crate::shim::*;
 use crate::units::*;
+#[cfg(feature = "async")]
+use crate::waker_list::*;
+
+/// Number of usable bits in an event group, the top 8 bits are reserved by the kernel.
+pub const EVENT_GROUP_MAX_BITS: u32 = FreeRtosEventBitsType::BITS - 8;
+
+/// Number of usable bits of the kernel's event groups, 8 with `configUSE_16_BIT_TICKS`
+/// and [`EVENT_GROUP_MAX_BITS`] otherwise.
+pub fn event_group_max_bits() -> u32 {
+    let size = unsafe { freertos_rs_sizeof(13) } as u32;
+    (size * 8 - 8).min(EVENT_GROUP_MAX_BITS)
+}
+
+/// A set of flags stored in an [`EventGroup`].
+///
+/// Implemented by [`EventBits`] and, with the `bitflags` feature,
+/// by every `bitflags` type with `u32` bits.
+pub trait EventFlags: Copy {
+    fn bits(&self) -> FreeRtosEventBitsType;
+    /// Convert from raw bits, keeping bits that don't correspond to a flag.
+    fn from_bits_retain(bits: FreeRtosEventBitsType) -> Self;
+}
+
+/// Raw event bits, the flags of an untyped [`EventGroup`].
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct EventBits(pub FreeRtosEventBitsType);
+
+impl EventFlags for EventBits {
+    #[inline]
+    fn bits(&self) -> FreeRtosEventBitsType {
+        self.0
+    }
+
+    #[inline]
+    fn from_bits_retain(bits: FreeRtosEventBitsType) -> Self {
+        Self(bits)
+    }
+}
+
+#[cfg(feature = "bitflags")]
+impl<T> EventFlags for T
+where
+    T: bitflags::Flags<Bits = FreeRtosEventBitsType> + Copy,
+{
+    #[inline]
+    fn bits(&self) -> FreeRtosEventBitsType {
+        bitflags::Flags::bits(self)
+    }
+
+    #[inline]
+    fn from_bits_retain(bits: FreeRtosEventBitsType) -> Self {
+        <T as bitflags::Flags>::from_bits_retain(bits)
+    }
+}
 
 /// An event group
-pub struct EventGroup {
+///
+/// The flag type `F` only changes the typed methods, the raw `*_bits` methods are always available.
+///
+/// ```ignore
+/// bitflags! {
+///     #[derive(Clone, Copy)]
+///     struct Events: u32 {
+///         const RX = 1 << 0;
+///         const TX = 1 << 1;
+///     }
+/// }
+///
+/// let events = EventGroup::<Events>::create()?;
+/// events.set(Events::RX);
+/// let got = events.wait_any(Events::RX | Events::TX).clear_on_exit().wait(Duration::ms(10))?;
+/// ```
+///
+/// With the `async` feature, [`EventWait`] can also be awaited with `wait_async`.
+/// It is woken when flags are set through the same `EventGroup`, but not by
+/// [`sync`](EventGroup::sync), which sets and waits in one kernel call, nor by C code
+/// setting them on the raw handle.
+pub struct EventGroup<F: EventFlags = EventBits> {
     event_group: FreeRtosEventGroupHandle,
+    #[cfg(feature = "async")]
+    wakers: SharedWakerList,
+    _flags: PhantomData<F>,
 }
 
-unsafe impl Send for EventGroup {}
-unsafe impl Sync for EventGroup {}
+unsafe impl<F: EventFlags> Send for EventGroup<F> {}
+unsafe impl<F: EventFlags> Sync for EventGroup<F> {}
 
 impl EventGroup {
     /// Create a new event group
     pub fn new() -> Result<EventGroup, FreeRtosError> {
+        Self::create()
+    }
+}
+
+impl<F: EventFlags> EventGroup<F> {
+    /// Create a new event group for the flags `F`
+    pub fn create() -> Result<Self, FreeRtosError> {
         unsafe {
             let s = freertos_rs_event_group_create();
             if s == 0 as *const _ {
                 return Err(FreeRtosError::OutOfMemory);
             }
-            Ok(EventGroup { event_group: s })
+            Ok(Self::from_raw_handle(s))
         }
     }
 
@@ -29,6 +117,9 @@ impl EventGroup {
     pub unsafe fn from_raw_handle(handle: FreeRtosEventGroupHandle) -> Self {
         Self {
             event_group: handle,
+            #[cfg(feature = "async")]
+            wakers: SharedWakerList::new(),
+            _flags: PhantomData,
         }
     }
     #[inline]
@@ -36,8 +127,97 @@ impl EventGroup {
         self.event_group
     }
 
+    /// Set flags and return the flags at the time this call returns.
+    pub fn set(&self, flags: F) -> F {
+        F::from_bits_retain(self.set_bits(flags.bits()))
+    }
+
+    pub fn get(&self) -> F {
+        F::from_bits_retain(self.get_bits())
+    }
+
+    /// Clear flags and return the flags before they were cleared.
+    pub fn clear(&self, flags: F) -> F {
+        F::from_bits_retain(self.clear_bits(flags.bits()))
+    }
+
+    /// Wait until any of `flags` is set.
+    pub fn wait_any(&self, flags: F) -> EventWait<'_, F> {
+        EventWait::new(self, flags, false)
+    }
+
+    /// Wait until all of `flags` are set.
+    pub fn wait_all(&self, flags: F) -> EventWait<'_, F> {
+        EventWait::new(self, flags, true)
+    }
+
+    /// Get the flags from an interrupt.
+    pub fn get_from_isr(&self) -> F {
+        F::from_bits_retain(unsafe { freertos_rs_event_group_get_bits_isr(self.event_group) })
+    }
+
+    /// Set flags from an interrupt.
+    ///
+    /// The bits are set by the timer daemon task, so this requires a timer task
+    /// (`use_timer_task` of `freertos-build`) and the `trace-facility` feature.
+    /// Fails with [`FreeRtosError::QueueFull`] if the timer command queue is full.
+    ///
+    /// With the `async` feature, once `wait_async` was used on this `EventGroup`, the
+    /// bits are set through [`pend_function_call_from_isr`](crate::pend_function_call_from_isr)
+    /// so the futures are woken after them. It also fails with
+    /// [`FreeRtosError::QueueFull`] if all pend call slots are taken.
+    #[cfg(feature = "trace-facility")]
+    pub fn set_bits_from_isr(
+        &self,
+        context: &mut InterruptContext,
+        flags: F,
+    ) -> Result<(), FreeRtosError> {
+        #[cfg(feature = "async")]
+        {
+            let handle = self.event_group as usize;
+            let bits = flags.bits();
+            let apply = move || unsafe {
+                freertos_rs_event_group_set_bits(handle as FreeRtosEventGroupHandle, bits);
+            };
+            let task_woken = context.get_task_field_mut();
+            self.wakers
+                .wake_all_after_from_isr(context, apply, || self.set_bits_isr(flags, task_woken))
+        }
+        #[cfg(not(feature = "async"))]
+        self.set_bits_isr(flags, context.get_task_field_mut())
+    }
+
+    #[cfg(feature = "trace-facility")]
+    fn set_bits_isr(
+        &self,
+        flags: F,
+        task_woken: FreeRtosBaseTypeMutPtr,
+    ) -> Result<(), FreeRtosError> {
+        let res = unsafe {
+            freertos_rs_event_group_set_bits_isr(self.event_group, flags.bits(), task_woken)
+        };
+        if res != 0 {
+            return Err(FreeRtosError::QueueFull);
+        }
+        Ok(())
+    }
+
+    /// Clear flags from an interrupt, deferred to the timer daemon task like
+    /// [`set_bits_from_isr`](EventGroup::set_bits_from_isr).
+    #[cfg(feature = "trace-facility")]
+    pub fn clear_bits_from_isr(&self, flags: F) -> Result<(), FreeRtosError> {
+        let res = unsafe { freertos_rs_event_group_clear_bits_isr(self.event_group, flags.bits()) };
+        if res != 0 {
+            return Err(FreeRtosError::QueueFull);
+        }
+        Ok(())
+    }
+
     pub fn set_bits(&self, bits_to_set: FreeRtosEventBitsType) -> FreeRtosEventBitsType {
-        unsafe { freertos_rs_event_group_set_bits(self.event_group, bits_to_set) }
+        let bits = unsafe { freertos_rs_event_group_set_bits(self.event_group, bits_to_set) };
+        #[cfg(feature = "async")]
+        self.wakers.wake_all();
+        bits
     }
 
     pub fn get_bits(&self) -> FreeRtosEventBitsType {
@@ -83,10 +263,76 @@ impl EventGroup {
     }
 }
 
-impl Drop for EventGroup {
+impl<F: EventFlags> Drop for EventGroup<F> {
     fn drop(&mut self) {
         unsafe {
             freertos_rs_event_group_delete(self.event_group);
         }
     }
 }
+
+/// A wait for flags of an [`EventGroup`], made by [`EventGroup::wait_any`] or [`EventGroup::wait_all`].
+#[must_use = "call `wait` to block"]
+pub struct EventWait<'a, F: EventFlags> {
+    group: &'a EventGroup<F>,
+    flags: F,
+    all: bool,
+    clear_on_exit: bool,
+}
+
+impl<'a, F: EventFlags> EventWait<'a, F> {
+    fn new(group: &'a EventGroup<F>, flags: F, all: bool) -> Self {
+        Self {
+            group,
+            flags,
+            all,
+            clear_on_exit: false,
+        }
+    }
+
+    /// Clear the awaited flags when the wait succeeds.
+    pub fn clear_on_exit(mut self) -> Self {
+        self.clear_on_exit = true;
+        self
+    }
+
+    /// Block until the condition is met and return the flags at that time,
+    /// before they are cleared. Fails with [`FreeRtosError::Timeout`].
+    pub fn wait<D: DurationTicks>(self, max_wait: D) -> Result<F, FreeRtosError> {
+        self.wait_bits(max_wait)
+    }
+
+    fn wait_bits<D: DurationTicks>(&self, max_wait: D) -> Result<F, FreeRtosError> {
+        let wanted = self.flags.bits();
+        let bits = self.group.wait_bits(
+            wanted,
+            self.clear_on_exit as FreeRtosBaseType,
+            self.all as FreeRtosBaseType,
+            max_wait,
+        );
+
+        let met = if self.all {
+            bits & wanted == wanted
+        } else {
+            bits & wanted != 0
+        };
+        if met {
+            Ok(F::from_bits_retain(bits))
+        } else {
+            Err(FreeRtosError::Timeout)
+        }
+    }
+
+    /// Wait for the condition without blocking the task and return the flags at that
+    /// time, before they are cleared.
+    ///
+    /// Wakes from `set_bits_from_isr` are deferred to the timer daemon task like the
+    /// bits themselves.
+    #[cfg(feature = "async")]
+    pub async fn wait_async(self) -> F {
+        self.group
+            .wakers
+            .wait_for(|| self.wait_bits(Duration::zero()).ok())
+            .await
+    }
+}
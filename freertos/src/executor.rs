@@ -0,0 +1,254 @@
+use crate::base::*;
+use crate::base_type::v1::*;
+use crate::isr::*;
+use crate::shim::*;
+use crate::task::*;
+use crate::units::*;
+use alloc::task::Wake;
+use core::ffi::c_void;
+use core::future::Future;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicPtr, AtomicU8, AtomicU32, Ordering};
+use core::task::{Context, Poll, Waker};
+
+const EMPTY: u8 = 0;
+const SPAWNING: u8 = 1;
+const OCCUPIED: u8 = 2;
+
+/// Largest number of futures an [`Executor`] can hold.
+pub const EXECUTOR_MAX_FUTURES: usize = 32;
+
+type BoxFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+struct Slot {
+    state: AtomicU8,
+    future: UnsafeCell<Option<BoxFuture>>,
+}
+
+struct Shared<const N: usize> {
+    /// The task running the executor, null until it runs.
+    task: AtomicPtr<c_void>,
+    /// One bit per slot that needs to be polled.
+    ready: AtomicU32,
+    slots: [Slot; N],
+}
+
+// SAFETY: a future is only touched by the spawner that reserved its empty slot,
+// and by the executor task once the slot is occupied.
+unsafe impl<const N: usize> Sync for Shared<N> {}
+unsafe impl<const N: usize> Send for Shared<N> {}
+
+impl<const N: usize> Shared<N> {
+    fn spawn(&self, future: BoxFuture) -> Result<(), FreeRtosError> {
+        let (index, slot) = self
+            .slots
+            .iter()
+            .enumerate()
+            .find(|(_, slot)| {
+                slot.state
+                    .compare_exchange(EMPTY, SPAWNING, Ordering::Acquire, Ordering::Relaxed)
+                    .is_ok()
+            })
+            .ok_or(FreeRtosError::OutOfMemory)?;
+        unsafe { *slot.future.get() = Some(future) };
+        slot.state.store(OCCUPIED, Ordering::Release);
+        self.wake(index);
+        Ok(())
+    }
+
+    fn wake(&self, index: usize) {
+        self.ready.fetch_or(1 << index, Ordering::Release);
+        notify_task(self.task.load(Ordering::Acquire));
+    }
+}
+
+/// Unblock a task parked in [`CurrentTask::take_notification`].
+fn notify_task(handle: *mut c_void) {
+    if handle.is_null() {
+        return;
+    }
+    let task = unsafe { Task::from_raw_handle(handle) };
+    if is_in_isr() {
+        let mut context = InterruptContext::new();
+        let _ = task.notify_from_isr(&mut context, TaskNotification::Increment);
+    } else {
+        task.notify(TaskNotification::Increment);
+    }
+}
+
+struct SlotWaker<const N: usize> {
+    shared: Arc<Shared<N>>,
+    index: usize,
+}
+
+impl<const N: usize> Wake for SlotWaker<N> {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref()
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.shared.wake(self.index)
+    }
+}
+
+/// Runs `'static` futures on one FreeRTOS task.
+///
+/// Up to `N` futures, at most [`EXECUTOR_MAX_FUTURES`], can be spawned at the same time,
+/// before or while the executor runs. The executor task parks on its notification value
+/// when no future is ready, and wakers unblock it with [`Task::notify`] or
+/// [`Task::notify_from_isr`], so futures can be woken from interrupts. Code on the
+/// executor task must not use the task's notification for anything else.
+///
+/// ```ignore
+/// let executor = Executor::<4>::new();
+/// let spawner = executor.spawner();
+/// executor.spawn(async move {
+///     loop {
+///         let frame = uart.read_frame().await;
+///         spawner.spawn(handle(frame)).ok();
+///     }
+/// })?;
+/// executor.start(Task::new().name("async").stack_size(1024))?;
+/// ```
+pub struct Executor<const N: usize> {
+    shared: Arc<Shared<N>>,
+}
+
+impl<const N: usize> Executor<N> {
+    pub fn new() -> Self {
+        const {
+            assert!(
+                N > 0 && N <= EXECUTOR_MAX_FUTURES,
+                "an executor holds 1 to EXECUTOR_MAX_FUTURES futures"
+            )
+        };
+        Self {
+            shared: Arc::new(Shared {
+                task: AtomicPtr::new(core::ptr::null_mut()),
+                ready: AtomicU32::new(0),
+                slots: [const {
+                    Slot {
+                        state: AtomicU8::new(EMPTY),
+                        future: UnsafeCell::new(None),
+                    }
+                }; N],
+            }),
+        }
+    }
+
+    /// A handle to spawn futures from other tasks or from futures of this executor.
+    pub fn spawner(&self) -> Spawner<N> {
+        Spawner {
+            shared: self.shared.clone(),
+        }
+    }
+
+    /// Add a future, fails with [`FreeRtosError::OutOfMemory`] if `N` futures are running.
+    pub fn spawn<F>(&self, future: F) -> Result<(), FreeRtosError>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.shared.spawn(Box::pin(future))
+    }
+
+    /// Run the executor on a new task.
+    pub fn start(self, builder: &TaskBuilder) -> Result<Task, FreeRtosError> {
+        builder.start(move |_| self.run())
+    }
+
+    /// Run the executor on the current task, forever.
+    pub fn run(self) -> ! {
+        let shared = &*self.shared;
+        shared.task.store(
+            unsafe { freertos_rs_get_current_task() } as *mut c_void,
+            Ordering::Release,
+        );
+        let wakers: Vec<Waker> = (0..N)
+            .map(|index| {
+                Waker::from(Arc::new(SlotWaker {
+                    shared: self.shared.clone(),
+                    index,
+                }))
+            })
+            .collect();
+
+        loop {
+            let ready = shared.ready.swap(0, Ordering::Acquire);
+            if ready == 0 {
+                CurrentTask::take_notification(true, Duration::infinite());
+                continue;
+            }
+            for (index, slot) in shared.slots.iter().enumerate() {
+                if ready & (1 << index) == 0 || slot.state.load(Ordering::Acquire) != OCCUPIED {
+                    continue;
+                }
+                let future = unsafe { (*slot.future.get()).as_mut().unwrap() };
+                let mut cx = Context::from_waker(&wakers[index]);
+                if future.as_mut().poll(&mut cx).is_ready() {
+                    unsafe { *slot.future.get() = None };
+                    slot.state.store(EMPTY, Ordering::Release);
+                }
+            }
+        }
+    }
+}
+
+impl<const N: usize> Default for Executor<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Spawns futures on an [`Executor`], see [`Executor::spawner`].
+pub struct Spawner<const N: usize> {
+    shared: Arc<Shared<N>>,
+}
+
+impl<const N: usize> Clone for Spawner<N> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<const N: usize> Spawner<N> {
+    /// Add a future, fails with [`FreeRtosError::OutOfMemory`] if `N` futures are running.
+    pub fn spawn<F>(&self, future: F) -> Result<(), FreeRtosError>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.shared.spawn(Box::pin(future))
+    }
+}
+
+struct TaskWaker {
+    task: AtomicPtr<c_void>,
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref()
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        notify_task(self.task.load(Ordering::Relaxed))
+    }
+}
+
+/// Run a future to completion on the current task, which blocks while the future is pending.
+///
+/// Like the [`Executor`], this uses the notification of the current task.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = core::pin::pin!(future);
+    let waker = Waker::from(Arc::new(TaskWaker {
+        task: AtomicPtr::new(unsafe { freertos_rs_get_current_task() } as *mut c_void),
+    }));
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+        CurrentTask::take_notification(true, Duration::infinite());
+    }
+}
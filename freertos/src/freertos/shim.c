@@ -12,6 +12,19 @@ STM32 example:
 #include "task.h"
 #include "timers.h"
 
+#if (__IS_HOSTED == 1)
+#    if defined(_WIN32)
+#        include <windows.h>
+#    else
+#        include <time.h>
+#    endif
+#endif
+
+#if defined(configUSE_TICKLESS_IDLE) && (configUSE_TICKLESS_IDLE != 0)
+// The sleep hooks of the crate take and return a 32-bit tick count.
+typedef char freertos_rs_tickless_needs_32_bit_ticks[(sizeof(TickType_t) == sizeof(uint32_t)) ? 1 : -1];
+#endif
+
 // Just for testing
 void freertos_rs_invoke_configASSERT() {
     configASSERT(0);
@@ -54,6 +67,9 @@ uint8_t freertos_rs_sizeof(uint8_t _type) {
     case 12:
         return sizeof(TickType_t);
         break;
+    case 13:
+        return sizeof(EventBits_t);
+        break;
 
     case 20:
         return sizeof(TaskHandle_t);
@@ -94,8 +110,13 @@ uint8_t freertos_rs_sizeof(uint8_t _type) {
 }
 
 #if (INCLUDE_vTaskDelayUntil == 1)
-void freertos_rs_vTaskDelayUntil(TickType_t *pxPreviousWakeTime, TickType_t xTimeIncrement) {
-    vTaskDelayUntil(pxPreviousWakeTime, xTimeIncrement);
+// pdTRUE if the task blocked, pdFALSE if the wake time had already passed.
+// The wake time is kept in 32 bits like on the Rust side, TickType_t is wider on some ports.
+BaseType_t freertos_rs_xTaskDelayUntil(uint32_t *pxPreviousWakeTime, TickType_t xTimeIncrement) {
+    TickType_t xWakeTime = *pxPreviousWakeTime;
+    BaseType_t xDelayed = xTaskDelayUntil(&xWakeTime, xTimeIncrement);
+    *pxPreviousWakeTime = (uint32_t)xWakeTime;
+    return xDelayed;
 }
 #endif
 
@@ -113,6 +134,28 @@ TickType_t freertos_rs_xTaskGetTickCountFromISR() {
     return xTaskGetTickCountFromISR();
 }
 
+// The kernel counts tick overflows for its timeouts, combined with the tick count
+// that gives a counter that doesn't wrap. Shifting in two steps drops the overflow count
+// without undefined behavior on ports with 64-bit ticks, such as POSIX.
+static uint64_t freertos_rs_extend_tick_count(const TimeOut_t *timeout) {
+    uint64_t overflows = (UBaseType_t)timeout->xOverflowCount;
+    return ((overflows << (sizeof(TickType_t) * 4)) << (sizeof(TickType_t) * 4)) | timeout->xTimeOnEntering;
+}
+
+uint64_t freertos_rs_get_tick_count64() {
+    TimeOut_t timeout;
+    vTaskSetTimeOutState(&timeout);
+    return freertos_rs_extend_tick_count(&timeout);
+}
+
+uint64_t freertos_rs_get_tick_count64_from_isr() {
+    TimeOut_t timeout;
+    UBaseType_t saved = taskENTER_CRITICAL_FROM_ISR();
+    vTaskInternalSetTimeOutState(&timeout);
+    taskEXIT_CRITICAL_FROM_ISR(saved);
+    return freertos_rs_extend_tick_count(&timeout);
+}
+
 #if (configUSE_TRACE_FACILITY == 1)
 UBaseType_t freertos_rs_get_system_state(TaskStatus_t *const pxTaskStatusArray, const UBaseType_t uxArraySize,
                                          uint32_t *const pulTotalRunTime) {
@@ -130,6 +173,10 @@ TickType_t freertos_rs_get_portTICK_PERIOD_MS() {
     return portTICK_PERIOD_MS;
 }
 
+TickType_t freertos_rs_get_configTICK_RATE_HZ() {
+    return configTICK_RATE_HZ;
+}
+
 UBaseType_t freertos_rs_get_number_of_tasks() {
     return uxTaskGetNumberOfTasks();
 }
@@ -159,6 +206,13 @@ SemaphoreHandle_t freertos_rs_create_mutex() {
     return xSemaphoreCreateMutex();
 }
 
+#if (configSUPPORT_STATIC_ALLOCATION == 1)
+SemaphoreHandle_t freertos_rs_create_mutex_static(StaticSemaphore_t *buffer, size_t size) {
+    configASSERT(sizeof(StaticSemaphore_t) <= size);
+    return xSemaphoreCreateMutexStatic(buffer);
+}
+#endif
+
 SemaphoreHandle_t freertos_rs_create_binary_semaphore() {
     return xSemaphoreCreateBinary();
 }
@@ -169,10 +223,20 @@ SemaphoreHandle_t freertos_rs_create_counting_semaphore(UBaseType_t max, UBaseTy
 }
 #endif
 
+#if (INCLUDE_xSemaphoreGetMutexHolder == 1)
+TaskHandle_t freertos_rs_get_mutex_holder(SemaphoreHandle_t mutex) {
+    return xSemaphoreGetMutexHolder(mutex);
+}
+#endif
+
 void freertos_rs_delete_semaphore(SemaphoreHandle_t semaphore) {
     vSemaphoreDelete(semaphore);
 }
 
+UBaseType_t freertos_rs_get_semaphore_count(SemaphoreHandle_t semaphore) {
+    return uxSemaphoreGetCount(semaphore);
+}
+
 UBaseType_t freertos_rs_take_semaphore(SemaphoreHandle_t semaphore, UBaseType_t max) {
     if (xSemaphoreTake(semaphore, max) == pdTRUE) {
         return 0;
@@ -228,6 +292,22 @@ UBaseType_t freertos_rs_spawn_task(TaskFunction_t entry_point, void *pvParameter
     return 0;
 }
 
+#if (INCLUDE_uxTaskPriorityGet == 1)
+UBaseType_t freertos_rs_task_get_priority(TaskHandle_t task) {
+    return uxTaskPriorityGet(task);
+}
+
+UBaseType_t freertos_rs_task_get_base_priority(TaskHandle_t task) {
+    return uxTaskBasePriorityGet(task);
+}
+#endif
+
+#if (INCLUDE_vTaskPrioritySet == 1)
+void freertos_rs_task_set_priority(TaskHandle_t task, UBaseType_t priority) {
+    vTaskPrioritySet(task, priority);
+}
+#endif
+
 #if (INCLUDE_vTaskDelete == 1)
 void freertos_rs_delete_task(TaskHandle_t task) {
     vTaskDelete(task);
@@ -334,6 +414,19 @@ BaseType_t freertos_rs_task_notify(void *task, uint32_t value, uint8_t action) {
     return 0;
 }
 
+#if (configTASK_NOTIFICATION_ARRAY_ENTRIES < 2)
+#    error "configTASK_NOTIFICATION_ARRAY_ENTRIES must be at least 2, the last index is reserved"
+#endif
+#define FREERTOS_RS_SYNC_NOTIFY_INDEX (configTASK_NOTIFICATION_ARRAY_ENTRIES - 1)
+
+void freertos_rs_sync_notify_give(TaskHandle_t task) {
+    xTaskNotifyGiveIndexed(task, FREERTOS_RS_SYNC_NOTIFY_INDEX);
+}
+
+uint32_t freertos_rs_sync_notify_take(TickType_t wait) {
+    return ulTaskNotifyTakeIndexed(FREERTOS_RS_SYNC_NOTIFY_INDEX, pdTRUE, wait);
+}
+
 BaseType_t freertos_rs_task_notify_isr(void *task, uint32_t value, uint8_t action,
                                        BaseType_t *xHigherPriorityTaskWoken) {
     eNotifyAction eAction = freertos_rs_task_notify_action(action);
@@ -371,26 +464,31 @@ void freertos_rs_vTaskSetTaskNumber(TaskHandle_t task, const UBaseType_t value)
 
 #if (configUSE_TIMERS == 1)
 
-TimerHandle_t freertos_rs_timer_create(const char *const name, uint8_t name_len, const TickType_t period,
-                                       uint8_t auto_reload, void *const timer_id, TimerCallbackFunction_t callback) {
-    char c_name[configMAX_TASK_NAME_LEN] = {0};
-    for (int i = 0; i < name_len; i++) {
-        c_name[i] = name[i];
-
-        if (i == configMAX_TASK_NAME_LEN - 1) {
-            break;
-        }
-    }
-
+TimerHandle_t freertos_rs_timer_create(const char *const name, const TickType_t period, uint8_t auto_reload,
+                                       void *const timer_id, TimerCallbackFunction_t callback) {
     UBaseType_t timer_auto_reload = pdFALSE;
     if (auto_reload == 1) {
         timer_auto_reload = pdTRUE;
     }
 
-    TimerHandle_t handle = xTimerCreate(c_name, period, timer_auto_reload, timer_id, callback);
+    // The kernel keeps the name pointer, so it has to outlive the timer.
+    TimerHandle_t handle = xTimerCreate(name, period, timer_auto_reload, timer_id, callback);
     return handle;
 }
 
+#if (configSUPPORT_STATIC_ALLOCATION == 1)
+// `STATIC_TIMER_WORDS` of static_timer.rs, the size of the buffer the crate passes in.
+#define FREERTOS_RS_STATIC_TIMER_WORDS 16
+typedef char freertos_rs_static_timer_fits[(sizeof(StaticTimer_t) <= FREERTOS_RS_STATIC_TIMER_WORDS * sizeof(void *)) ? 1 : -1];
+
+TimerHandle_t freertos_rs_timer_create_static(const char *const name, const TickType_t period, uint8_t auto_reload,
+                                              void *const timer_id, TimerCallbackFunction_t callback,
+                                              StaticTimer_t *buffer, size_t size) {
+    configASSERT(sizeof(StaticTimer_t) <= size);
+    return xTimerCreateStatic(name, period, auto_reload == 1 ? pdTRUE : pdFALSE, timer_id, callback, buffer);
+}
+#endif
+
 BaseType_t freertos_rs_timer_start(TimerHandle_t timer, TickType_t block_time) {
     if (xTimerStart(timer, block_time) != pdPASS) {
         return 1;
@@ -430,6 +528,80 @@ void *freertos_rs_timer_get_id(TimerHandle_t timer) {
     return pvTimerGetTimerID(timer);
 }
 
+BaseType_t freertos_rs_timer_reset(TimerHandle_t timer, TickType_t block_time) {
+    if (xTimerReset(timer, block_time) != pdPASS) {
+        return 1;
+    }
+    return 0;
+}
+
+BaseType_t freertos_rs_timer_reset_from_isr(TimerHandle_t timer, BaseType_t *xHigherPriorityTaskWoken) {
+    if (xTimerResetFromISR(timer, xHigherPriorityTaskWoken) != pdPASS) {
+        return 1;
+    }
+    return 0;
+}
+
+BaseType_t freertos_rs_timer_stop_from_isr(TimerHandle_t timer, BaseType_t *xHigherPriorityTaskWoken) {
+    if (xTimerStopFromISR(timer, xHigherPriorityTaskWoken) != pdPASS) {
+        return 1;
+    }
+    return 0;
+}
+
+BaseType_t freertos_rs_timer_change_period_from_isr(TimerHandle_t timer, TickType_t new_period,
+                                                    BaseType_t *xHigherPriorityTaskWoken) {
+    if (xTimerChangePeriodFromISR(timer, new_period, xHigherPriorityTaskWoken) != pdPASS) {
+        return 1;
+    }
+    return 0;
+}
+
+BaseType_t freertos_rs_timer_is_active(TimerHandle_t timer) {
+    return xTimerIsTimerActive(timer) != pdFALSE;
+}
+
+TickType_t freertos_rs_timer_get_period(TimerHandle_t timer) {
+    return xTimerGetPeriod(timer);
+}
+
+TickType_t freertos_rs_timer_get_expiry_time(TimerHandle_t timer) {
+    return xTimerGetExpiryTime(timer);
+}
+
+void freertos_rs_timer_set_reload_mode(TimerHandle_t timer, uint8_t auto_reload) {
+    vTimerSetReloadMode(timer, auto_reload == 1 ? pdTRUE : pdFALSE);
+}
+
+uint8_t freertos_rs_timer_get_reload_mode(TimerHandle_t timer) {
+    return xTimerGetReloadMode(timer) != pdFALSE;
+}
+
+const char *freertos_rs_timer_get_name(TimerHandle_t timer) {
+    return pcTimerGetName(timer);
+}
+
+// Returns 1 if the call could not be queued, or if the kernel was built without it.
+BaseType_t freertos_rs_timer_pend_function_call(PendedFunction_t function, void *parameter1, uint32_t parameter2,
+                                                TickType_t block_time) {
+#if (INCLUDE_xTimerPendFunctionCall == 1)
+    if (xTimerPendFunctionCall(function, parameter1, parameter2, block_time) == pdPASS) {
+        return 0;
+    }
+#endif
+    return 1;
+}
+
+BaseType_t freertos_rs_timer_pend_function_call_isr(PendedFunction_t function, void *parameter1, uint32_t parameter2,
+                                                    BaseType_t *xHigherPriorityTaskWoken) {
+#if (INCLUDE_xTimerPendFunctionCall == 1)
+    if (xTimerPendFunctionCallFromISR(function, parameter1, parameter2, xHigherPriorityTaskWoken) == pdPASS) {
+        return 0;
+    }
+#endif
+    return 1;
+}
+
 #endif
 
 BaseType_t freertos_rs_is_in_isr() {
@@ -483,6 +655,28 @@ EventBits_t freertos_rs_event_group_sync(EventGroupHandle_t event_group, const E
     return xEventGroupSync(event_group, bits_to_set, bits_to_wait_for, ticks_to_wait);
 }
 
+EventBits_t freertos_rs_event_group_get_bits_isr(EventGroupHandle_t event_group) {
+    return xEventGroupGetBitsFromISR(event_group);
+}
+
+// The ISR setters defer the work to the timer daemon task.
+#if ((configUSE_TRACE_FACILITY == 1) && (INCLUDE_xTimerPendFunctionCall == 1) && (configUSE_TIMERS == 1))
+BaseType_t freertos_rs_event_group_set_bits_isr(EventGroupHandle_t event_group, const EventBits_t bits_to_set,
+                                                BaseType_t *xHigherPriorityTaskWoken) {
+    if (xEventGroupSetBitsFromISR(event_group, bits_to_set, xHigherPriorityTaskWoken) == pdPASS) {
+        return 0;
+    }
+    return 1;
+}
+
+BaseType_t freertos_rs_event_group_clear_bits_isr(EventGroupHandle_t event_group, const EventBits_t bits_to_clear) {
+    if (xEventGroupClearBitsFromISR(event_group, bits_to_clear) == pdPASS) {
+        return 0;
+    }
+    return 1;
+}
+#endif
+
 #if (INCLUDE_HeapFreeSize == 1)
 size_t freertos_rs_get_free_heap_size() {
     return xPortGetFreeHeapSize();
@@ -496,3 +690,20 @@ void freertos_rs_reset_minimum_free_heap_size() {
     xPortResetHeapMinimumEverFreeHeapSize();
 }
 #endif
+
+#if (__IS_HOSTED == 1)
+// A monotonic clock of the host, for timeouts finer than a tick on the simulator ports.
+uint64_t freertos_rs_host_monotonic_us() {
+#    if defined(_WIN32)
+    LARGE_INTEGER frequency, counter;
+    QueryPerformanceFrequency(&frequency);
+    QueryPerformanceCounter(&counter);
+    return (uint64_t)(counter.QuadPart / frequency.QuadPart) * 1000000u +
+           (uint64_t)(counter.QuadPart % frequency.QuadPart) * 1000000u / (uint64_t)frequency.QuadPart;
+#    else
+    struct timespec now;
+    clock_gettime(CLOCK_MONOTONIC, &now);
+    return (uint64_t)now.tv_sec * 1000000u + (uint64_t)now.tv_nsec / 1000u;
+#    endif
+}
+#endif
@@ -0,0 +1,205 @@
+use crate::base::*;
+use crate::base_type::v1::*;
+use crate::task::*;
+use crate::units::*;
+use core::ops::{Add, AddAssign, Sub, SubAssign};
+
+/// A point in time, measured in ticks of the scheduler.
+///
+/// The tick counter wraps around, so instants have no order and the difference between
+/// two of them is only correct if they are less than one full counter period apart.
+/// That is about 49 days at a 1 kHz tick with 32-bit ticks.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Instant {
+    tick: FreeRtosTickType,
+}
+
+impl Instant {
+    /// The current tick count, can be called from an ISR.
+    pub fn now() -> Self {
+        Self::from_ticks(FreeRtosUtils::get_tick_count())
+    }
+
+    pub const fn from_ticks(tick: FreeRtosTickType) -> Self {
+        Self { tick }
+    }
+
+    pub const fn ticks(&self) -> FreeRtosTickType {
+        self.tick
+    }
+
+    /// Time passed since this instant.
+    pub fn elapsed(&self) -> Duration {
+        Self::now().duration_since(*self)
+    }
+
+    /// Time passed from `earlier` to this instant.
+    pub fn duration_since(&self, earlier: Instant) -> Duration {
+        Duration::ticks(self.tick.wrapping_sub(earlier.tick))
+    }
+}
+
+impl<D: DurationTicks> Add<D> for Instant {
+    type Output = Instant;
+
+    fn add(self, rhs: D) -> Instant {
+        Instant::from_ticks(self.tick.wrapping_add(rhs.to_ticks()))
+    }
+}
+
+impl<D: DurationTicks> AddAssign<D> for Instant {
+    fn add_assign(&mut self, rhs: D) {
+        *self = *self + rhs;
+    }
+}
+
+impl<D: DurationTicks> Sub<D> for Instant {
+    type Output = Instant;
+
+    fn sub(self, rhs: D) -> Instant {
+        Instant::from_ticks(self.tick.wrapping_sub(rhs.to_ticks()))
+    }
+}
+
+impl<D: DurationTicks> SubAssign<D> for Instant {
+    fn sub_assign(&mut self, rhs: D) {
+        *self = *self - rhs;
+    }
+}
+
+/// A point in time, measured in ticks since the scheduler started, that never wraps.
+///
+/// The 32-bit tick count is extended with the number of times it has overflowed, which
+/// the kernel keeps for its own timeouts. Unlike [`Instant`], these are ordered.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Instant64 {
+    tick: u64,
+}
+
+impl Instant64 {
+    /// The current tick count, can be called from an ISR.
+    pub fn now() -> Self {
+        Self::from_ticks(FreeRtosUtils::get_tick_count64())
+    }
+
+    pub const fn from_ticks(tick: u64) -> Self {
+        Self { tick }
+    }
+
+    pub const fn ticks(&self) -> u64 {
+        self.tick
+    }
+
+    /// Time passed since this instant.
+    pub fn elapsed(&self) -> Duration {
+        Self::now().duration_since(*self)
+    }
+
+    /// Time passed from `earlier` to this instant, zero if `earlier` is later and
+    /// [`Duration::infinite`] if it doesn't fit.
+    pub fn duration_since(&self, earlier: Instant64) -> Duration {
+        let ticks = self.tick.saturating_sub(earlier.tick);
+        Duration::ticks(ticks.min(FreeRtosTickType::MAX as u64) as FreeRtosTickType)
+    }
+
+    /// The lower half of the tick count, as used by the kernel.
+    pub fn to_instant(&self) -> Instant {
+        Instant::from_ticks(self.tick as FreeRtosTickType)
+    }
+
+    /// Time since the scheduler started.
+    pub fn since_start(&self) -> core::time::Duration {
+        let rate = FreeRtosTimeUnitsShimmed::get_tick_rate_hz() as u64;
+        let secs = self.tick / rate;
+        let nanos = (self.tick % rate) * 1_000_000_000 / rate;
+        core::time::Duration::new(secs, nanos as u32)
+    }
+}
+
+impl<D: DurationTicks> Add<D> for Instant64 {
+    type Output = Instant64;
+
+    fn add(self, rhs: D) -> Instant64 {
+        Instant64::from_ticks(self.tick.saturating_add(rhs.to_ticks() as u64))
+    }
+}
+
+impl<D: DurationTicks> AddAssign<D> for Instant64 {
+    fn add_assign(&mut self, rhs: D) {
+        *self = *self + rhs;
+    }
+}
+
+impl<D: DurationTicks> Sub<D> for Instant64 {
+    type Output = Instant64;
+
+    fn sub(self, rhs: D) -> Instant64 {
+        Instant64::from_ticks(self.tick.saturating_sub(rhs.to_ticks() as u64))
+    }
+}
+
+impl<D: DurationTicks> SubAssign<D> for Instant64 {
+    fn sub_assign(&mut self, rhs: D) {
+        *self = *self - rhs;
+    }
+}
+
+/// Time since the scheduler started, for timestamps. Can be called from an ISR.
+pub fn uptime() -> core::time::Duration {
+    Instant64::now().since_start()
+}
+
+/// One wait budget shared by several blocking calls.
+///
+/// Passed as the timeout of a blocking call, a deadline gives the ticks that are left of
+/// the budget, so a sequence of calls fails once the budget is used up as a whole.
+/// A deadline made from [`Duration::infinite`] never expires.
+///
+/// ```ignore
+/// let deadline = Deadline::after(Duration::ms(50));
+/// let request = requests.receive(deadline)?;
+/// let mut state = state.lock(deadline)?;
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Deadline {
+    start: Instant,
+    ticks: FreeRtosTickType,
+}
+
+impl Deadline {
+    /// A deadline `max_wait` from now.
+    pub fn after<D: DurationTicks>(max_wait: D) -> Self {
+        Self {
+            start: Instant::now(),
+            ticks: max_wait.to_ticks(),
+        }
+    }
+
+    /// A deadline that never expires.
+    pub fn never() -> Self {
+        Self::after(Duration::infinite())
+    }
+
+    /// The time left until the deadline, zero once it has passed.
+    pub fn remaining(&self) -> Duration {
+        Duration::ticks(self.to_ticks())
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.to_ticks() == 0
+    }
+
+    /// When the deadline was made.
+    pub fn start(&self) -> Instant {
+        self.start
+    }
+}
+
+impl DurationTicks for Deadline {
+    fn to_ticks(&self) -> FreeRtosTickType {
+        if self.ticks == FreeRtosTimeUnitsShimmed::get_max_wait() {
+            return self.ticks;
+        }
+        self.ticks.saturating_sub(self.start.elapsed().to_ticks())
+    }
+}
@@ -6,6 +6,8 @@
 
 #[cfg_attr(any(feature = "time", feature = "sync"), macro_use)]
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
 pub mod prelude;
 
@@ -15,33 +17,70 @@ mod shim;
 
 #[cfg(feature = "allocator")]
 mod allocator;
+#[cfg(feature = "sync")]
+mod barrier;
 mod base;
+#[cfg(all(feature = "sync", feature = "task-priority"))]
+mod ceiling_mutex;
+#[cfg(feature = "sync")]
+mod condvar;
 #[cfg(feature = "sync")]
 mod critical;
 #[cfg(feature = "time")]
 mod delays;
 #[cfg(feature = "sync")]
 mod event_group;
+#[cfg(feature = "async")]
+mod executor;
+#[cfg(any(feature = "time", feature = "sync"))]
+mod instant;
 #[cfg(feature = "interrupt")]
 mod isr;
 #[cfg(feature = "sync")]
 mod mutex;
-#[cfg(cortex_m)]
+#[cfg(feature = "mutex-debug")]
+mod mutex_debug;
+#[cfg(all(any(cortex_m, hosted), feature = "sync"))]
 mod os_trait_impls;
+#[cfg(feature = "time")]
+mod pend_call;
+#[cfg(feature = "sync")]
+mod poison;
 #[cfg(feature = "sync")]
 mod queue;
 #[cfg(feature = "sync")]
+mod raw_mutex;
+#[cfg(all(feature = "sync", feature = "counting-semaphore"))]
+mod resource_pool;
+#[cfg(all(feature = "sync", feature = "counting-semaphore"))]
+mod rwlock;
+#[cfg(feature = "sync")]
 mod semaphore;
+mod sleep_hooks;
+#[cfg(feature = "static-allocation")]
+mod static_mutex;
+#[cfg(all(feature = "time", feature = "static-allocation"))]
+mod static_timer;
 #[cfg(any(feature = "time", feature = "sync"))]
 mod task;
+#[cfg(feature = "embassy-time-driver")]
+mod time_driver;
+#[cfg(all(feature = "sync", feature = "time"))]
+mod timer_wheel;
 #[cfg(feature = "time")]
 mod timers;
 #[cfg(any(feature = "time", feature = "sync"))]
 mod units;
 mod utils;
+#[cfg(feature = "sync")]
+mod wait_queue;
+#[cfg(feature = "async")]
+mod waker_list;
 
 #[cfg(feature = "sync")]
 pub mod patterns;
+#[cfg(feature = "sync")]
+pub mod sync;
 
 // Internal stuff that is only public for first Proof of Concept
 pub use crate::base::*;
@@ -52,25 +91,54 @@ pub use os_trait::{self, os_type_alias};
 #[cfg(feature = "allocator")]
 pub use crate::allocator::*;
 pub use crate::assert_callback::*;
+#[cfg(feature = "sync")]
+pub use crate::barrier::*;
 pub use crate::base::FreeRtosError;
+#[cfg(all(feature = "sync", feature = "task-priority"))]
+pub use crate::ceiling_mutex::*;
+#[cfg(feature = "sync")]
+pub use crate::condvar::*;
 #[cfg(feature = "sync")]
 pub use crate::critical::*;
 #[cfg(feature = "time")]
 pub use crate::delays::*;
 #[cfg(feature = "sync")]
 pub use crate::event_group::*;
+#[cfg(feature = "async")]
+pub use crate::executor::*;
+#[cfg(any(feature = "time", feature = "sync"))]
+pub use crate::instant::*;
 #[cfg(feature = "interrupt")]
 pub use crate::isr::*;
 #[cfg(feature = "sync")]
 pub use crate::mutex::*;
-#[cfg(cortex_m)]
+#[cfg(feature = "mutex-debug")]
+pub use crate::mutex_debug::*;
+#[cfg(all(any(cortex_m, hosted), feature = "sync"))]
 pub use crate::os_trait_impls::*;
+#[cfg(feature = "time")]
+pub use crate::pend_call::*;
+#[cfg(feature = "poison")]
+pub use crate::poison::*;
 #[cfg(feature = "sync")]
 pub use crate::queue::*;
 #[cfg(feature = "sync")]
+pub use crate::raw_mutex::*;
+#[cfg(all(feature = "sync", feature = "counting-semaphore"))]
+pub use crate::resource_pool::*;
+#[cfg(all(feature = "sync", feature = "counting-semaphore"))]
+pub use crate::rwlock::*;
+#[cfg(feature = "sync")]
 pub use crate::semaphore::*;
+pub use crate::sleep_hooks::*;
+#[cfg(feature = "static-allocation")]
+pub use crate::static_mutex::*;
+#[cfg(all(feature = "time", feature = "static-allocation"))]
+pub use crate::static_timer::*;
 #[cfg(any(feature = "time", feature = "sync"))]
 pub use crate::task::*;
+#[cfg(all(feature = "sync", feature = "time"))]
+pub use crate::timer_wheel::*;
 #[cfg(feature = "time")]
 pub use crate::timers::*;
 #[cfg(any(feature = "time", feature = "sync"))]
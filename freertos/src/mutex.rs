@@ -1,7 +1,15 @@
 use crate::base::*;
 use crate::base_type::v1::*;
+#[cfg(feature = "mutex-debug")]
+use crate::mutex_debug::*;
+use crate::poison::*;
 use crate::shim::*;
+use crate::task::*;
 use crate::units::*;
+#[cfg(feature = "async")]
+use crate::waker_list::*;
+use core::ffi::c_void;
+use core::sync::atomic::{AtomicPtr, AtomicU32, Ordering};
 
 pub type Mutex<T> = MutexImpl<T, MutexNormal>;
 pub type RecursiveMutex<T> = MutexImpl<T, MutexRecursive>;
@@ -14,6 +22,11 @@ unsafe impl<T: Send, M> Sync for MutexImpl<T, M> {}
 /// the current owner of a lock can re-lock it.
 pub struct MutexImpl<T: ?Sized, M> {
     mutex: M,
+    #[cfg(feature = "mutex-debug")]
+    debug: MutexDebugState,
+    #[cfg(feature = "async")]
+    wakers: WakerList,
+    poison: PoisonFlag,
     data: UnsafeCell<T>,
 }
 
@@ -36,18 +49,89 @@ where
     }
 
     /// Try to obtain a lock and mutable access to our inner value
+    ///
+    /// A lock taken after a holder panicked is released again and reported as
+    /// [`FreeRtosError::MutexPoisoned`], see [`lock_poisoned`](MutexImpl::lock_poisoned)
+    /// to get the guard anyway.
     pub fn lock<D: DurationTicks>(
         &self,
         max_wait: D,
     ) -> Result<MutexGuard<'_, T, M>, FreeRtosError> {
+        let guard = self.take_guard(max_wait)?;
+        if self.poison.get() {
+            return Err(FreeRtosError::MutexPoisoned);
+        }
+        Ok(guard)
+    }
+
+    /// Try to obtain the lock without waiting.
+    pub fn try_lock(&self) -> Result<MutexGuard<'_, T, M>, FreeRtosError> {
+        self.lock(Duration::zero())
+    }
+
+    /// Like [`lock`](MutexImpl::lock), but a lock taken after a holder panicked is
+    /// returned as `LockError::Poisoned`, which still contains the guard.
+    #[cfg(feature = "poison")]
+    pub fn lock_poisoned<D: DurationTicks>(&self, max_wait: D) -> LockResult<MutexGuard<'_, T, M>> {
+        let guard = self.take_guard(max_wait).map_err(LockError::Timeout)?;
+        if self.poison.get() {
+            return Err(LockError::Poisoned(PoisonError::new(guard)));
+        }
+        Ok(guard)
+    }
+
+    /// Like [`try_lock`](MutexImpl::try_lock), see [`lock_poisoned`](MutexImpl::lock_poisoned).
+    #[cfg(feature = "poison")]
+    pub fn try_lock_poisoned(&self) -> LockResult<MutexGuard<'_, T, M>> {
+        self.lock_poisoned(Duration::zero())
+    }
+
+    fn take_guard<D: DurationTicks>(
+        &self,
+        max_wait: D,
+    ) -> Result<MutexGuard<'_, T, M>, FreeRtosError> {
+        #[cfg(feature = "mutex-debug")]
+        self.debug.take(&self.mutex, max_wait)?;
+        #[cfg(not(feature = "mutex-debug"))]
         self.mutex.take(max_wait)?;
 
         Ok(MutexGuard {
-            __mutex: &self.mutex,
+            __lock: HeldLock {
+                mutex: &self.mutex,
+                #[cfg(feature = "mutex-debug")]
+                debug: &self.debug,
+                #[cfg(feature = "async")]
+                wakers: &self.wakers,
+                #[cfg(feature = "poison")]
+                poison: &self.poison,
+                #[cfg(feature = "poison")]
+                panicking: panicking(),
+            },
             __data: &self.data,
         })
     }
 
+    /// Whether a task panicked while holding the lock. Needs the `poison` feature.
+    pub fn is_poisoned(&self) -> bool {
+        self.poison.get()
+    }
+
+    /// Clear the poisoned state, after the data has been checked or repaired.
+    pub fn clear_poison(&self) {
+        self.poison.clear();
+    }
+
+    /// Get the task that currently holds the lock.
+    pub fn holder(&self) -> Option<Task> {
+        mutex_holder(self.mutex.raw_handle())
+    }
+
+    /// Get the recorded holder, lock time and waiting tasks of this mutex.
+    #[cfg(feature = "mutex-debug")]
+    pub fn debug_info(&self) -> MutexDebugInfo {
+        self.debug.info()
+    }
+
     /// Consume the mutex and return its inner value
     pub fn into_inner(self) -> T {
         self.into_parts().1
@@ -63,9 +147,14 @@ where
     /// Create owning mutex from non-owning mutex and inner value.
     ///
     /// It is safe to pass an already locked `mutex` although it is not recommended.
-    pub fn from_parts(mutex: M, value: T) -> Self {
+    pub const fn from_parts(mutex: M, value: T) -> Self {
         Self {
             mutex,
+            #[cfg(feature = "mutex-debug")]
+            debug: MutexDebugState::new(),
+            #[cfg(feature = "async")]
+            wakers: WakerList::new(),
+            poison: PoisonFlag::new(),
             data: UnsafeCell::new(value),
         }
     }
@@ -81,15 +170,117 @@ where
     }
 }
 
+impl<T> MutexImpl<T, MutexRecursive> {
+    /// How many times the holder has locked the mutex without unlocking it, 0 if it is free.
+    pub fn lock_depth(&self) -> u32 {
+        self.mutex.lock_depth()
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T> MutexImpl<T, MutexNormal> {
+    /// Wait for the lock without blocking the task, woken when a guard of this mutex
+    /// is dropped.
+    ///
+    /// The kernel gives the lock to the task that polls the future, so the guard has
+    /// to be dropped on that task. A recursive mutex has no such method, because it
+    /// would let two futures polled by one task hold the lock at the same time.
+    ///
+    /// Fails only with [`FreeRtosError::MutexPoisoned`], like [`lock`](MutexImpl::lock).
+    pub async fn lock_async(&self) -> Result<MutexGuard<'_, T, MutexNormal>, FreeRtosError> {
+        self.wakers
+            .wait_for(|| match self.try_lock() {
+                Err(FreeRtosError::MutexPoisoned) => Some(Err(FreeRtosError::MutexPoisoned)),
+                Err(_) => None,
+                result => Some(result),
+            })
+            .await
+    }
+}
+
+/// A taken mutex, given back when dropped.
+struct HeldLock<'a, M: MutexInnerImpl> {
+    mutex: &'a M,
+    #[cfg(feature = "mutex-debug")]
+    debug: &'a MutexDebugState,
+    #[cfg(feature = "async")]
+    wakers: &'a WakerList,
+    #[cfg(feature = "poison")]
+    poison: &'a PoisonFlag,
+    #[cfg(feature = "poison")]
+    panicking: bool,
+}
+
+impl<'a, M: MutexInnerImpl> HeldLock<'a, M> {
+    fn give(&self) {
+        #[cfg(feature = "mutex-debug")]
+        self.debug.give();
+        self.mutex.give();
+        #[cfg(feature = "async")]
+        self.wakers.wake_all();
+    }
+
+    fn take(&self) {
+        loop {
+            #[cfg(feature = "mutex-debug")]
+            let taken = self.debug.take(self.mutex, Duration::infinite());
+            #[cfg(not(feature = "mutex-debug"))]
+            let taken = self.mutex.take(Duration::infinite());
+            if taken.is_ok() {
+                return;
+            }
+        }
+    }
+}
+
+impl<'a, M: MutexInnerImpl> Drop for HeldLock<'a, M> {
+    fn drop(&mut self) {
+        #[cfg(feature = "poison")]
+        self.poison.done(self.panicking);
+        self.give();
+    }
+}
+
 /// Holds the mutex until we are dropped
 pub struct MutexGuard<'a, T: ?Sized + 'a, M: 'a>
 where
     M: MutexInnerImpl,
 {
-    __mutex: &'a M,
+    __lock: HeldLock<'a, M>,
     __data: &'a UnsafeCell<T>,
 }
 
+impl<'a, T: ?Sized, M> MutexGuard<'a, T, M>
+where
+    M: MutexInnerImpl,
+{
+    /// Make a guard for a part of the locked data, such as a field.
+    ///
+    /// This is an associated function, call it as `MutexGuard::map(guard, f)`.
+    pub fn map<U: ?Sized, F>(orig: Self, f: F) -> MappedMutexGuard<'a, U, M>
+    where
+        F: FnOnce(&mut T) -> &mut U,
+    {
+        let data = f(unsafe { &mut *orig.__data.get() }) as *mut U;
+        MappedMutexGuard {
+            __lock: orig.__lock,
+            __data: data,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Give the mutex back without dropping the guard, it must be
+    /// [`reacquire`](MutexGuard::reacquire)d before the guard is used again.
+    pub(crate) fn release(&self) {
+        self.__lock.give();
+    }
+
+    /// Wait for the mutex given back by [`release`](MutexGuard::release) for as long as it takes.
+    pub(crate) fn reacquire(&self) {
+        self.__lock.take();
+    }
+}
+
 impl<'mutex, T: ?Sized, M> Deref for MutexGuard<'mutex, T, M>
 where
     M: MutexInnerImpl,
@@ -110,12 +301,33 @@ where
     }
 }
 
-impl<'a, T: ?Sized, M> Drop for MutexGuard<'a, T, M>
+/// A guard for a part of the data of a mutex, made by [`MutexGuard::map`].
+pub struct MappedMutexGuard<'a, T: ?Sized + 'a, M: 'a>
 where
     M: MutexInnerImpl,
 {
-    fn drop(&mut self) {
-        self.__mutex.give();
+    __lock: HeldLock<'a, M>,
+    __data: *mut T,
+    _marker: PhantomData<&'a mut T>,
+}
+
+impl<T: ?Sized, M> Deref for MappedMutexGuard<'_, T, M>
+where
+    M: MutexInnerImpl,
+{
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.__data }
+    }
+}
+
+impl<T: ?Sized, M> DerefMut for MappedMutexGuard<'_, T, M>
+where
+    M: MutexInnerImpl,
+{
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.__data }
     }
 }
 
@@ -137,8 +349,25 @@ where
     fn raw_handle(&self) -> FreeRtosSemaphoreHandle;
 }
 
+pub(crate) fn mutex_holder(handle: FreeRtosSemaphoreHandle) -> Option<Task> {
+    let holder = unsafe { freertos_rs_get_mutex_holder(handle) };
+    if holder.is_null() {
+        None
+    } else {
+        Some(unsafe { Task::from_raw_handle(holder) })
+    }
+}
+
 pub struct MutexNormal(FreeRtosSemaphoreHandle);
 
+#[cfg(feature = "static-allocation")]
+impl MutexNormal {
+    /// A mutex without a kernel object, to be replaced before it is used.
+    pub(crate) const fn empty() -> Self {
+        Self(core::ptr::null())
+    }
+}
+
 impl MutexInnerImpl for MutexNormal {
     fn create() -> Result<Self, FreeRtosError> {
         let m = unsafe { freertos_rs_create_mutex() };
@@ -177,7 +406,10 @@ impl MutexInnerImpl for MutexNormal {
 
 impl Drop for MutexNormal {
     fn drop(&mut self) {
-        unsafe { freertos_rs_delete_semaphore(self.0) }
+        // A `StaticMutex` that was never locked has no handle yet.
+        if !self.0.is_null() {
+            unsafe { freertos_rs_delete_semaphore(self.0) }
+        }
     }
 }
 
@@ -187,7 +419,19 @@ impl fmt::Debug for MutexNormal {
     }
 }
 
-pub struct MutexRecursive(FreeRtosSemaphoreHandle);
+pub struct MutexRecursive {
+    handle: FreeRtosSemaphoreHandle,
+    depth: AtomicU32,
+}
+
+impl MutexRecursive {
+    /// How many times the holder has locked the mutex without unlocking it, 0 if it is free.
+    ///
+    /// Only locks taken through this instance are counted.
+    pub fn lock_depth(&self) -> u32 {
+        self.depth.load(Ordering::Relaxed)
+    }
+}
 
 #[cfg(feature = "recursive-mutex")]
 impl MutexInnerImpl for MutexRecursive {
@@ -196,92 +440,108 @@ impl MutexInnerImpl for MutexRecursive {
         if m == 0 as *const _ {
             return Err(FreeRtosError::OutOfMemory);
         }
-        Ok(MutexRecursive(m))
+        Ok(unsafe { Self::from_raw_handle(m) })
     }
 
     fn take<D: DurationTicks>(&self, max_wait: D) -> Result<(), FreeRtosError> {
-        let res = unsafe { freertos_rs_take_recursive_semaphore(self.0, max_wait.to_ticks()) };
+        let res = unsafe { freertos_rs_take_recursive_semaphore(self.handle, max_wait.to_ticks()) };
 
         if res != 0 {
             return Err(FreeRtosError::MutexTimeout);
         }
 
+        self.depth.fetch_add(1, Ordering::Relaxed);
         Ok(())
     }
 
     fn give(&self) {
+        self.depth.fetch_sub(1, Ordering::Relaxed);
         unsafe {
-            freertos_rs_give_recursive_semaphore(self.0);
+            freertos_rs_give_recursive_semaphore(self.handle);
         }
     }
 
     #[inline]
     unsafe fn from_raw_handle(handle: FreeRtosSemaphoreHandle) -> Self {
-        Self(handle)
+        Self {
+            handle,
+            depth: AtomicU32::new(0),
+        }
     }
 
     #[inline]
     fn raw_handle(&self) -> FreeRtosSemaphoreHandle {
-        self.0
+        self.handle
     }
 }
 
 #[cfg(feature = "recursive-mutex")]
 impl Drop for MutexRecursive {
     fn drop(&mut self) {
-        unsafe { freertos_rs_delete_semaphore(self.0) }
+        unsafe { freertos_rs_delete_semaphore(self.handle) }
     }
 }
 
 impl fmt::Debug for MutexRecursive {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}", self.0)
+        write!(f, "{:?}", self.handle)
     }
 }
 
 use mutex_traits::{ConstInit, RawMutex};
 /// `mutex-traits` implementation
 pub struct FreeRtosRawMutex {
-    inner: UnsafeCell<FreeRtosSemaphoreHandle>,
+    /// Created on first use.
+    inner: AtomicPtr<c_void>,
 }
 
 impl FreeRtosRawMutex {
     /// Create a new `FreeRtosRawMutex`.
     pub const fn empty() -> Self {
         Self {
-            inner: UnsafeCell::new(core::ptr::null()),
+            inner: AtomicPtr::new(core::ptr::null_mut()),
         }
     }
 
-    fn get_inner(&self) -> &FreeRtosSemaphoreHandle {
-        let inner = unsafe { &mut *self.inner.get() };
-        if (*inner).is_null() {
-            critical_section::with(|_| {
-                if (*inner).is_null() {
-                    let m = unsafe { freertos_rs_create_mutex() };
-                    if m.is_null() {
-                        panic!();
-                    }
-                    *inner = m;
-                }
-            });
+    fn get_inner(&self) -> FreeRtosSemaphoreHandle {
+        let inner = self.inner.load(Ordering::Acquire);
+        if !inner.is_null() {
+            return inner;
+        }
+
+        let m = unsafe { freertos_rs_create_mutex() } as *mut c_void;
+        if m.is_null() {
+            panic!();
+        }
+        match self.inner.compare_exchange(
+            core::ptr::null_mut(),
+            m,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            Ok(_) => m,
+            Err(existing) => {
+                unsafe { freertos_rs_delete_semaphore(m) };
+                existing
+            }
         }
-        inner
     }
 
     #[inline]
-    fn try_lock_block(&self, block: bool) -> bool {
+    pub(crate) fn try_lock_block(&self, block: bool) -> bool {
         let t = if block {
             FreeRtosTimeUnitsShimmed::get_max_wait()
         } else {
             0
         };
-        let res = unsafe { freertos_rs_take_semaphore(*self.get_inner(), t) };
+        let res = unsafe { freertos_rs_take_semaphore(self.get_inner(), t) };
         res == 0
     }
 }
 
 unsafe impl Send for FreeRtosRawMutex {}
+// SAFETY: the handle is an atomic, published once by `get_inner`.
+unsafe impl Sync for FreeRtosRawMutex {}
 
 impl ConstInit for FreeRtosRawMutex {
     const INIT: Self = Self::empty();
@@ -305,7 +565,7 @@ unsafe impl RawMutex for FreeRtosRawMutex {
     #[inline]
     unsafe fn unlock(&self) {
         unsafe {
-            freertos_rs_give_semaphore(*self.get_inner());
+            freertos_rs_give_semaphore(self.get_inner());
         }
     }
 
@@ -324,6 +584,9 @@ unsafe impl RawMutex for FreeRtosRawMutex {
 
 impl Drop for FreeRtosRawMutex {
     fn drop(&mut self) {
-        unsafe { freertos_rs_delete_semaphore(*self.get_inner()) }
+        let inner = *self.inner.get_mut();
+        if !inner.is_null() {
+            unsafe { freertos_rs_delete_semaphore(inner) }
+        }
     }
 }
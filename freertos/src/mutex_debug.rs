@@ -0,0 +1,255 @@
+//! Lock bookkeeping for [`MutexImpl`](crate::MutexImpl), enabled by the `mutex-debug` feature.
+//!
+//! Every mutex records its holder, the tick it was locked at and the tasks waiting for it.
+//! All blocked `lock` calls are also entered into a global wait-for graph, so a lock-order
+//! cycle between tasks is detected before the task starts waiting and is reported
+//! through [`MUTEX_DEBUG_HOOKS`].
+
+use crate::base::*;
+use crate::base_type::v1::*;
+use crate::critical::*;
+use crate::mutex::*;
+use crate::shim::*;
+use crate::task::*;
+use crate::units::*;
+use core::sync::atomic::{AtomicPtr, AtomicU32, Ordering};
+
+/// Maximum number of waiting tasks recorded per mutex.
+pub const MUTEX_DEBUG_MAX_WAITERS: usize = 8;
+/// Maximum number of tasks that can be blocked on a mutex at the same time
+/// and still be tracked by the deadlock detection.
+pub const MUTEX_DEBUG_MAX_BLOCKED: usize = 32;
+
+type DeadlockCallback = fn(&[Task]);
+
+pub struct MutexDebugHooks {
+    /// A `DeadlockCallback`, null until it is set.
+    on_deadlock: AtomicPtr<()>,
+    untracked: AtomicU32,
+}
+
+impl MutexDebugHooks {
+    /// Set the callback invoked when a lock-order cycle is detected, it can only be set once.
+    ///
+    /// It receives the tasks of the cycle, the first one is the task that is about to block,
+    /// every task waits for a mutex held by the next one and the last one holds the mutex
+    /// the first one waits for. The callback runs on the task that closes the cycle.
+    pub fn set_on_deadlock(&self, c: DeadlockCallback) -> Result<(), DeadlockCallback> {
+        self.on_deadlock
+            .compare_exchange(
+                core::ptr::null_mut(),
+                c as *mut (),
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            )
+            .map(|_| ())
+            .map_err(|_| c)
+    }
+
+    /// Number of times a task blocked on a mutex while [`MUTEX_DEBUG_MAX_BLOCKED`] tasks
+    /// were already blocked. Deadlocks that involve such a wait are not detected.
+    pub fn untracked_waits(&self) -> u32 {
+        self.untracked.load(Ordering::Relaxed)
+    }
+
+    fn do_on_deadlock(&self, cycle: &[Task]) {
+        let cb = self.on_deadlock.load(Ordering::Acquire);
+        if !cb.is_null() {
+            // SAFETY: only `set_on_deadlock` stores a non-null value, a `DeadlockCallback`.
+            let cb: DeadlockCallback = unsafe { mem::transmute(cb) };
+            cb(cycle)
+        }
+    }
+}
+
+pub static MUTEX_DEBUG_HOOKS: MutexDebugHooks = MutexDebugHooks {
+    on_deadlock: AtomicPtr::new(core::ptr::null_mut()),
+    untracked: AtomicU32::new(0),
+};
+
+/// Snapshot of the lock state of a mutex.
+#[derive(Debug, Clone)]
+pub struct MutexDebugInfo {
+    pub holder: Option<Task>,
+    /// Tick count at which the current holder took the lock.
+    pub locked_at: Option<FreeRtosTickType>,
+    pub waiters: Vec<Task>,
+    /// Waiting tasks that are not in `waiters` because it already held
+    /// [`MUTEX_DEBUG_MAX_WAITERS`] tasks.
+    pub untracked_waiters: u32,
+}
+
+struct DebugState {
+    holder: FreeRtosTaskHandle,
+    locked_at: FreeRtosTickType,
+    depth: u32,
+    waiters: [FreeRtosTaskHandle; MUTEX_DEBUG_MAX_WAITERS],
+    untracked_waiters: u32,
+}
+
+/// Per-mutex lock bookkeeping, only accessed inside a critical region.
+pub(crate) struct MutexDebugState {
+    state: UnsafeCell<DebugState>,
+}
+
+impl MutexDebugState {
+    pub(crate) const fn new() -> Self {
+        Self {
+            state: UnsafeCell::new(DebugState {
+                holder: core::ptr::null(),
+                locked_at: 0,
+                depth: 0,
+                waiters: [core::ptr::null(); MUTEX_DEBUG_MAX_WAITERS],
+                untracked_waiters: 0,
+            }),
+        }
+    }
+
+    fn with<R>(&self, f: impl FnOnce(&mut DebugState) -> R) -> R {
+        let _lock = CriticalRegion::enter();
+        f(unsafe { &mut *self.state.get() })
+    }
+
+    pub(crate) fn take<M: MutexInnerImpl, D: DurationTicks>(
+        &self,
+        mutex: &M,
+        max_wait: D,
+    ) -> Result<(), FreeRtosError> {
+        let current = unsafe { freertos_rs_get_current_task() };
+
+        if mutex.take(Duration::zero()).is_err() {
+            let handle = mutex.raw_handle();
+            self.with(|s| match s.waiters.iter_mut().find(|w| w.is_null()) {
+                Some(w) => *w = current,
+                None => s.untracked_waiters += 1,
+            });
+            if BLOCKED.insert(current, handle) {
+                detect_deadlock(current, handle);
+            } else {
+                MUTEX_DEBUG_HOOKS.untracked.fetch_add(1, Ordering::Relaxed);
+            }
+
+            let res = mutex.take(max_wait);
+
+            BLOCKED.remove(current);
+            self.with(|s| match s.waiters.iter_mut().find(|w| **w == current) {
+                Some(w) => *w = core::ptr::null(),
+                None => s.untracked_waiters = s.untracked_waiters.saturating_sub(1),
+            });
+
+            if res.is_err() {
+                return Err(match mutex_holder(handle) {
+                    Some(task) => FreeRtosError::MutexTimeoutHeldBy(task.name()),
+                    None => FreeRtosError::MutexTimeout,
+                });
+            }
+        }
+
+        let now = FreeRtosUtils::get_tick_count();
+        self.with(|s| {
+            if s.depth == 0 {
+                s.holder = current;
+                s.locked_at = now;
+            }
+            s.depth += 1;
+        });
+        Ok(())
+    }
+
+    /// Must be called by the holder right before the mutex is given back.
+    pub(crate) fn give(&self) {
+        self.with(|s| {
+            s.depth = s.depth.saturating_sub(1);
+            if s.depth == 0 {
+                s.holder = core::ptr::null();
+            }
+        });
+    }
+
+    pub(crate) fn info(&self) -> MutexDebugInfo {
+        let (holder, locked_at, waiters, untracked_waiters) =
+            self.with(|s| (s.holder, s.locked_at, s.waiters, s.untracked_waiters));
+        let to_task = |h: FreeRtosTaskHandle| unsafe { Task::from_raw_handle(h) };
+        MutexDebugInfo {
+            holder: (!holder.is_null()).then(|| to_task(holder)),
+            locked_at: (!holder.is_null()).then_some(locked_at),
+            waiters: waiters
+                .iter()
+                .filter(|w| !w.is_null())
+                .map(|w| to_task(*w))
+                .collect(),
+            untracked_waiters,
+        }
+    }
+}
+
+/// The edges of the wait-for graph: which task is blocked on which mutex.
+struct BlockedTasks {
+    entries: UnsafeCell<[(FreeRtosTaskHandle, FreeRtosSemaphoreHandle); MUTEX_DEBUG_MAX_BLOCKED]>,
+}
+
+// SAFETY: only accessed inside a critical region.
+unsafe impl Sync for BlockedTasks {}
+
+static BLOCKED: BlockedTasks = BlockedTasks {
+    entries: UnsafeCell::new([(core::ptr::null(), core::ptr::null()); MUTEX_DEBUG_MAX_BLOCKED]),
+};
+
+impl BlockedTasks {
+    fn with<R>(
+        &self,
+        f: impl FnOnce(&mut [(FreeRtosTaskHandle, FreeRtosSemaphoreHandle)]) -> R,
+    ) -> R {
+        let _lock = CriticalRegion::enter();
+        f(unsafe { &mut *self.entries.get() })
+    }
+
+    /// Returns `false` if all entries are taken.
+    fn insert(&self, task: FreeRtosTaskHandle, mutex: FreeRtosSemaphoreHandle) -> bool {
+        self.with(|entries| {
+            let Some(e) = entries.iter_mut().find(|e| e.0.is_null()) else {
+                return false;
+            };
+            *e = (task, mutex);
+            true
+        })
+    }
+
+    fn remove(&self, task: FreeRtosTaskHandle) {
+        self.with(|entries| {
+            if let Some(e) = entries.iter_mut().find(|e| e.0 == task) {
+                *e = (core::ptr::null(), core::ptr::null());
+            }
+        });
+    }
+
+    fn waiting_for(&self, task: FreeRtosTaskHandle) -> Option<FreeRtosSemaphoreHandle> {
+        self.with(|entries| entries.iter().find(|e| e.0 == task).map(|e| e.1))
+    }
+}
+
+/// Follow the holder chain starting at `mutex` and report if it leads back to `current`.
+///
+/// A cycle has at most one task per entry of the wait-for graph, so it fits on the stack.
+fn detect_deadlock(current: FreeRtosTaskHandle, mutex: FreeRtosSemaphoreHandle) {
+    let mut cycle: [Task; MUTEX_DEBUG_MAX_BLOCKED] =
+        core::array::from_fn(|_| unsafe { Task::from_raw_handle(current) });
+    let mut len = 1;
+    let mut mutex = mutex;
+
+    while len < MUTEX_DEBUG_MAX_BLOCKED {
+        let Some(holder) = mutex_holder(mutex) else {
+            return;
+        };
+        if holder.raw_handle() == current {
+            MUTEX_DEBUG_HOOKS.do_on_deadlock(&cycle[..len]);
+            return;
+        }
+        let Some(next) = BLOCKED.waiting_for(holder.raw_handle()) else {
+            return;
+        };
+        cycle[len] = holder;
+        len += 1;
+        mutex = next;
+    }
+}
@@ -1,29 +1,40 @@
 use crate::*;
-use core::{cell::UnsafeCell, marker::PhantomData};
+use core::ffi::c_void;
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicPtr, Ordering};
 use os_trait::{
     DelayNs, Duration as OsDuration, FakeRawMutex, NotifierInterface, NotifyWaiterInterface,
     TickInstant, TickTimeout, prelude::*,
 };
 
 /// `OsInterface` implementation, the N can be choose between [`SemaphoreNotifier`]
-pub struct FreeRTOS<N> {
+/// and [`TaskNotifier`].
+///
+/// The `I` is the time source of timeouts, [`FreeRtosInstant`] by default. On cores with
+/// a DWT it can be `DwtInstant`, which keeps working when SysTick isn't the kernel tick.
+pub struct FreeRTOS<N, I = FreeRtosInstant> {
     _n: PhantomData<N>,
+    _i: PhantomData<I>,
 }
 
-unsafe impl<N> Send for FreeRTOS<N> {}
-unsafe impl<N> Sync for FreeRTOS<N> {}
+unsafe impl<N, I> Send for FreeRTOS<N, I> {}
+unsafe impl<N, I> Sync for FreeRTOS<N, I> {}
 
-impl<N> OsInterface for FreeRTOS<N>
+impl<N, I> OsInterface for FreeRTOS<N, I>
 where
-    N: NotifyBuilder,
+    N: NotifyBuilder<I>,
+    I: TickInstant + 'static,
 {
     type RawMutex = FakeRawMutex;
     type Notifier = N::Notifier;
     type NotifyWaiter = N::Waiter;
-    type Instant = FreeRtosInstant;
+    type Instant = I;
     type Delay = FreeRtosDelayNs;
 
-    const O: Self = Self { _n: PhantomData };
+    const O: Self = Self {
+        _n: PhantomData,
+        _i: PhantomData,
+    };
 
     #[inline]
     fn yield_thread() {
@@ -41,9 +52,9 @@ where
     }
 }
 
-pub trait NotifyBuilder: Sized + 'static {
+pub trait NotifyBuilder<I: TickInstant + 'static = FreeRtosInstant>: Sized + 'static {
     type Notifier: NotifierInterface;
-    type Waiter: NotifyWaiterInterface<FreeRTOS<Self>>;
+    type Waiter: NotifyWaiterInterface<FreeRTOS<Self, I>>;
 
     fn build() -> (Self::Notifier, Self::Waiter);
 }
@@ -51,26 +62,28 @@ pub trait NotifyBuilder: Sized + 'static {
 /// `NotifyBuilder` implementation for task notification
 #[derive(Clone)]
 pub struct TaskNotifier {
-    inner: Arc<UnsafeCell<Task>>,
+    inner: Arc<AtomicPtr<c_void>>,
 }
 
-unsafe impl Send for TaskNotifier {}
-unsafe impl Sync for TaskNotifier {}
-
 impl TaskNotifier {
     pub fn new() -> (Self, TaskNotifyWaiter) {
-        let task = unsafe { Task::from_raw_handle(core::ptr::null()) };
-        let inner = Arc::new(UnsafeCell::new(task));
+        let inner = Arc::new(AtomicPtr::new(core::ptr::null_mut()));
         let inner2 = Arc::clone(&inner);
         (Self { inner }, TaskNotifyWaiter { inner: inner2 })
     }
+}
 
-    fn get_inner(&self) -> &mut Task {
-        unsafe { &mut *self.inner.get() }
+/// The task that waits, it is registered by its first wait.
+fn waiting_task(inner: &AtomicPtr<c_void>) -> Option<Task> {
+    let handle = inner.load(Ordering::Acquire);
+    if handle.is_null() {
+        None
+    } else {
+        Some(unsafe { Task::from_raw_handle(handle) })
     }
 }
 
-impl NotifyBuilder for TaskNotifier {
+impl<I: TickInstant + 'static> NotifyBuilder<I> for TaskNotifier {
     type Notifier = TaskNotifier;
     type Waiter = TaskNotifyWaiter;
 
@@ -81,55 +94,38 @@ impl NotifyBuilder for TaskNotifier {
 
 impl NotifierInterface for TaskNotifier {
     fn notify(&self) -> bool {
-        let inner = self.get_inner();
-        if inner.is_null() {
+        let Some(task) = waiting_task(&self.inner) else {
             return false;
-        }
+        };
 
         if is_in_isr() {
             let mut ctx = InterruptContext::new();
-            inner
-                .notify_from_isr(&mut ctx, TaskNotification::OverwriteValue(1))
+            task.notify_from_isr(&mut ctx, TaskNotification::OverwriteValue(1))
                 .is_ok()
         } else {
-            inner.set_notification_value(1);
+            task.set_notification_value(1);
             true
         }
     }
 }
 
 pub struct TaskNotifyWaiter {
-    inner: Arc<UnsafeCell<Task>>,
-}
-
-impl TaskNotifyWaiter {
-    fn get_inner(&self) -> &mut Task {
-        unsafe { &mut *self.inner.get() }
-    }
+    inner: Arc<AtomicPtr<c_void>>,
 }
 
-unsafe impl Send for TaskNotifyWaiter {}
-
 impl<OS: OsInterface> NotifyWaiterInterface<OS> for TaskNotifyWaiter {
     fn wait(&self, timeout: &OsDuration<OS>) -> bool {
-        let inner = self.get_inner();
-
         if let Ok(task) = Task::current() {
-            if *inner != task {
-                critical_section::with(|_| {
-                    if *inner != task {
-                        *inner = task;
-                    }
-                });
-            }
+            self.inner
+                .store(task.raw_handle() as *mut c_void, Ordering::Release);
         }
 
-        if inner.is_null() {
+        let Some(task) = waiting_task(&self.inner) else {
             return false;
-        }
+        };
 
         let dur = Duration::ms(timeout.as_millis());
-        if let Ok(val) = inner.wait_for_notification(0, u32::MAX, dur) {
+        if let Ok(val) = task.wait_for_notification(0, u32::MAX, dur) {
             return val != 0;
         }
         false
@@ -152,7 +148,7 @@ impl SemaphoreNotifier {
     }
 }
 
-impl NotifyBuilder for SemaphoreNotifier {
+impl<I: TickInstant + 'static> NotifyBuilder<I> for SemaphoreNotifier {
     type Notifier = SemaphoreNotifier;
     type Waiter = SemaphoreNotifyWaiter;
 
@@ -289,4 +285,152 @@ mod sys_tick_timeout {
     }
 }
 
+#[cfg(has_dwt)]
+pub use dwt_timeout::*;
+#[cfg(has_dwt)]
+mod dwt_timeout {
+    use super::*;
+    use core::cell::Cell;
+    use cortex_m::peripheral::{DCB, DWT};
+    use os_trait::{KilohertzU32, TickDuration};
+
+    const DEMCR_TRCENA: u32 = 1 << 24;
+    const DWT_CTRL_CYCCNTENA: u32 = 1;
+
+    /// The cycle count extended to 64 bits, as of the last read.
+    static CYCLES: critical_section::Mutex<Cell<u64>> = critical_section::Mutex::new(Cell::new(0));
+
+    /// `TickInstant` counting CPU cycles with the DWT cycle counter of Cortex-M3/M4/M7/M33.
+    ///
+    /// It doesn't depend on SysTick, so it keeps working with tickless idle or another
+    /// tick source. The 32-bit `CYCCNT` is extended to 64 bits on each read, which only
+    /// notices an overflow if it is read at least once per overflow period, about 43
+    /// seconds at 100 MHz. Run-time stats, see [`DwtInstant::run_time_counter`], or any
+    /// periodic timeout check take care of that. The counter doesn't run while the core
+    /// sleeps on some parts.
+    ///
+    /// Call [`DwtInstant::enable`] before the first use, then select it as the instant
+    /// of [`FreeRTOS`]:
+    ///
+    /// ```ignore
+    /// DwtInstant::enable();
+    /// os_type_alias!(FreeRTOS<SemaphoreNotifier, DwtInstant>);
+    /// ```
+    #[derive(Clone)]
+    pub struct DwtInstant {
+        cycles: u64,
+    }
+
+    impl DwtInstant {
+        /// Start the cycle counter. Also called by the kernel when the run-time stats use it.
+        pub fn enable() {
+            unsafe {
+                (*DCB::PTR).demcr.modify(|r| r | DEMCR_TRCENA);
+                // Parts like the STM32F7 lock the DWT after reset.
+                DWT::unlock();
+                (*DWT::PTR).ctrl.modify(|r| r | DWT_CTRL_CYCCNTENA);
+            }
+        }
+
+        /// Cycles since the counter was enabled, can be called from an ISR.
+        pub fn cycles() -> u64 {
+            critical_section::with(|cs| {
+                let last = CYCLES.borrow(cs);
+                let previous = last.get();
+                let now = DWT::cycle_count();
+                let mut extended = (previous & !0xFFFF_FFFF) | now as u64;
+                if now < previous as u32 {
+                    extended += 1 << 32;
+                }
+                last.set(extended);
+                extended
+            })
+        }
+
+        /// The microseconds of the cycle count, as the 32-bit run-time stats counter.
+        pub fn run_time_counter() -> u32 {
+            let hz = utils::cpu_clock_hz() as u64;
+            let cycles = Self::cycles();
+            // Split so that clocks below 1 MHz work and the product can't overflow.
+            (cycles / hz * 1_000_000 + cycles % hz * 1_000_000 / hz) as u32
+        }
+    }
+
+    impl TickInstant for DwtInstant {
+        #[inline]
+        fn frequency() -> KilohertzU32 {
+            utils::cpu_clock_hz().Hz()
+        }
+
+        #[inline]
+        fn now() -> Self {
+            Self {
+                cycles: Self::cycles(),
+            }
+        }
+
+        #[inline]
+        fn elapsed(&mut self) -> TickDuration<Self> {
+            TickDuration::from_ticks(Self::cycles().saturating_sub(self.cycles))
+        }
+
+        #[inline]
+        fn move_forward(&mut self, dur: &TickDuration<Self>) {
+            self.cycles += dur.as_ticks();
+        }
+    }
+
+    /// `portCONFIGURE_TIMER_FOR_RUN_TIME_STATS` of `freertos-build`'s `run_time_stats_dwt`.
+    #[unsafe(no_mangle)]
+    pub extern "C" fn freertos_rs_dwt_configure_run_time_counter() {
+        DwtInstant::enable();
+    }
+
+    /// `portGET_RUN_TIME_COUNTER_VALUE` of `freertos-build`'s `run_time_stats_dwt`.
+    #[unsafe(no_mangle)]
+    pub extern "C" fn freertos_rs_dwt_run_time_counter() -> u32 {
+        DwtInstant::run_time_counter()
+    }
+}
+
+#[cfg(hosted)]
+pub use host_timeout::*;
+#[cfg(hosted)]
+mod host_timeout {
+    use super::*;
+    use os_trait::{KilohertzU32, TickDuration};
+
+    /// `TickInstant` of the POSIX and Windows ports, counting microseconds of the host's
+    /// monotonic clock.
+    #[derive(Clone)]
+    pub struct FreeRtosInstant {
+        us: u64,
+    }
+
+    impl TickInstant for FreeRtosInstant {
+        #[inline]
+        fn frequency() -> KilohertzU32 {
+            1.MHz()
+        }
+
+        #[inline]
+        fn now() -> Self {
+            Self {
+                us: unsafe { freertos_rs_host_monotonic_us() },
+            }
+        }
+
+        #[inline]
+        fn elapsed(&mut self) -> TickDuration<Self> {
+            let now = unsafe { freertos_rs_host_monotonic_us() };
+            TickDuration::from_ticks(now.saturating_sub(self.us))
+        }
+
+        #[inline]
+        fn move_forward(&mut self, dur: &TickDuration<Self>) {
+            self.us += dur.as_ticks();
+        }
+    }
+}
+
 // TODO more implementation
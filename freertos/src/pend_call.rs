@@ -0,0 +1,131 @@
+use crate::base::*;
+use crate::base_type::v1::*;
+use crate::isr::*;
+use crate::shim::*;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// Number of closures that can wait for the timer daemon task at the same time.
+pub const PEND_CALL_SLOTS: usize = 8;
+/// Maximum size, in words, of a closure passed to [`pend_function_call`].
+pub const PEND_CALL_INLINE_WORDS: usize = 4;
+
+type Storage = [usize; PEND_CALL_INLINE_WORDS];
+
+/// A preallocated place for one pending closure, so that pending from an ISR
+/// doesn't need the heap.
+struct Slot {
+    busy: AtomicBool,
+    call: UnsafeCell<unsafe fn(&Slot)>,
+    storage: UnsafeCell<MaybeUninit<Storage>>,
+}
+
+// SAFETY: `call` and `storage` are only accessed by the owner of `busy`.
+unsafe impl Sync for Slot {}
+
+impl Slot {
+    const fn new() -> Self {
+        Self {
+            busy: AtomicBool::new(false),
+            call: UnsafeCell::new(no_call),
+            storage: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    fn closure<F>(&self) -> *mut F {
+        unsafe { (*self.storage.get()).as_mut_ptr().cast() }
+    }
+}
+
+static SLOTS: [Slot; PEND_CALL_SLOTS] = [const { Slot::new() }; PEND_CALL_SLOTS];
+
+unsafe fn no_call(_: &Slot) {}
+
+unsafe fn call_slot<F: FnOnce()>(slot: &Slot) {
+    let f = unsafe { slot.closure::<F>().read() };
+    slot.busy.store(false, Ordering::Release);
+    f()
+}
+
+extern "C" fn run_slot(slot: FreeRtosMutVoidPtr, _: u32) {
+    unsafe {
+        let slot = &*(slot as *const Slot);
+        (*slot.call.get())(slot)
+    }
+}
+
+fn pend<F, S>(f: F, send: S) -> Result<(), FreeRtosError>
+where
+    F: FnOnce() + Send + 'static,
+    S: FnOnce(FreeRtosMutVoidPtr) -> FreeRtosBaseType,
+{
+    const {
+        assert!(
+            mem::size_of::<F>() <= mem::size_of::<Storage>()
+                && mem::align_of::<F>() <= mem::align_of::<Storage>(),
+            "the closure captures more than PEND_CALL_INLINE_WORDS words"
+        )
+    };
+
+    let slot = SLOTS
+        .iter()
+        .find(|slot| {
+            slot.busy
+                .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+                .is_ok()
+        })
+        .ok_or(FreeRtosError::OutOfMemory)?;
+
+    unsafe {
+        slot.closure::<F>().write(f);
+        *slot.call.get() = call_slot::<F>;
+    }
+
+    if send(slot as *const Slot as FreeRtosMutVoidPtr) != 0 {
+        unsafe { drop(slot.closure::<F>().read()) };
+        slot.busy.store(false, Ordering::Release);
+        return Err(FreeRtosError::QueueFull);
+    }
+    Ok(())
+}
+
+/// Run `f` in the timer daemon task.
+///
+/// The closure is stored in one of [`PEND_CALL_SLOTS`] preallocated slots and may
+/// capture up to [`PEND_CALL_INLINE_WORDS`] words, which is checked at compile time.
+/// Fails with [`FreeRtosError::OutOfMemory`] if all slots are in use and with
+/// [`FreeRtosError::QueueFull`] if the timer command queue is full, see `use_timer_task`
+/// of `freertos-build`. Never blocks.
+pub fn pend_function_call<F>(f: F) -> Result<(), FreeRtosError>
+where
+    F: FnOnce() + Send + 'static,
+{
+    pend(f, |slot| unsafe {
+        freertos_rs_timer_pend_function_call(run_slot, slot, 0, 0)
+    })
+}
+
+/// Run `f` in the timer daemon task, deferring work from an interrupt.
+///
+/// Works like [`pend_function_call`] and doesn't use the heap. The daemon task is
+/// switched to when the interrupt returns if it has a higher priority than the
+/// interrupted task.
+///
+/// ```ignore
+/// fn uart_isr() {
+///     let mut context = InterruptContext::new();
+///     let byte = UART.read();
+///     let _ = pend_function_call_from_isr(&mut context, move || handle_byte(byte));
+/// }
+/// ```
+pub fn pend_function_call_from_isr<F>(
+    context: &mut InterruptContext,
+    f: F,
+) -> Result<(), FreeRtosError>
+where
+    F: FnOnce() + Send + 'static,
+{
+    pend(f, |slot| unsafe {
+        freertos_rs_timer_pend_function_call_isr(run_slot, slot, 0, context.get_task_field_mut())
+    })
+}
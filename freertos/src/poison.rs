@@ -0,0 +1,156 @@
+//! Mutex poisoning, behind the `poison` feature.
+//!
+//! A panic is detected with `std::thread::panicking`, so the feature needs `std` and
+//! only does something on targets that unwind, such as the host ports. On `no_std`
+//! targets a panic doesn't unwind into the guard's `drop`, so there is nothing to detect.
+
+#[cfg(feature = "poison")]
+use crate::base::*;
+#[cfg(feature = "poison")]
+use crate::base_type::v1::*;
+#[cfg(feature = "poison")]
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// Result of [`lock_poisoned`](crate::MutexImpl::lock_poisoned).
+///
+/// The plain `lock` methods return a [`FreeRtosError`] either way, so enabling the
+/// feature doesn't change their signature for other crates.
+#[cfg(feature = "poison")]
+pub type LockResult<G> = Result<G, LockError<G>>;
+
+/// Error returned when a mutex could not be locked.
+#[cfg(feature = "poison")]
+pub enum LockError<G> {
+    /// The mutex was not released in time.
+    Timeout(FreeRtosError),
+    /// The mutex was locked, but a previous holder panicked while holding it.
+    Poisoned(PoisonError<G>),
+}
+
+#[cfg(feature = "poison")]
+impl<G> fmt::Debug for LockError<G> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LockError::Timeout(e) => f.debug_tuple("Timeout").field(e).finish(),
+            LockError::Poisoned(e) => f.debug_tuple("Poisoned").field(e).finish(),
+        }
+    }
+}
+
+#[cfg(feature = "poison")]
+impl<G> From<LockError<G>> for FreeRtosError {
+    fn from(e: LockError<G>) -> Self {
+        match e {
+            LockError::Timeout(e) => e,
+            LockError::Poisoned(_) => FreeRtosError::MutexPoisoned,
+        }
+    }
+}
+
+/// A lock that was taken although the mutex is poisoned. The guard is still usable.
+#[cfg(feature = "poison")]
+pub struct PoisonError<G> {
+    guard: G,
+}
+
+#[cfg(feature = "poison")]
+impl<G> PoisonError<G> {
+    pub fn new(guard: G) -> Self {
+        Self { guard }
+    }
+
+    /// Ignore the poisoning and get the guard.
+    pub fn into_inner(self) -> G {
+        self.guard
+    }
+
+    pub fn get_ref(&self) -> &G {
+        &self.guard
+    }
+
+    pub fn get_mut(&mut self) -> &mut G {
+        &mut self.guard
+    }
+}
+
+#[cfg(feature = "poison")]
+impl<G> fmt::Debug for PoisonError<G> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PoisonError").finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "poison")]
+impl<G> fmt::Display for PoisonError<G> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("poisoned lock: another task panicked while holding it")
+    }
+}
+
+/// Apply `f` to the guard of `result`, also when it is inside a `PoisonError`.
+#[cfg(all(feature = "poison", feature = "task-priority"))]
+pub(crate) fn map_lock_result<G, U>(
+    result: LockResult<G>,
+    f: impl FnOnce(G) -> U,
+) -> LockResult<U> {
+    match result {
+        Ok(guard) => Ok(f(guard)),
+        Err(LockError::Poisoned(e)) => {
+            Err(LockError::Poisoned(PoisonError::new(f(e.into_inner()))))
+        }
+        Err(LockError::Timeout(e)) => Err(LockError::Timeout(e)),
+    }
+}
+
+/// Take the guard even if the lock is poisoned.
+#[cfg(all(feature = "poison", feature = "time"))]
+pub(crate) fn ignore_poison<G>(result: LockResult<G>) -> Result<G, FreeRtosError> {
+    match result {
+        Ok(guard) => Ok(guard),
+        Err(LockError::Poisoned(e)) => Ok(e.into_inner()),
+        Err(LockError::Timeout(e)) => Err(e),
+    }
+}
+
+/// Records whether a task panicked while holding a lock.
+///
+/// Detecting a panic needs `std`, so without the `poison` feature the flag is never set.
+pub(crate) struct PoisonFlag {
+    #[cfg(feature = "poison")]
+    failed: AtomicBool,
+}
+
+impl PoisonFlag {
+    pub(crate) const fn new() -> Self {
+        Self {
+            #[cfg(feature = "poison")]
+            failed: AtomicBool::new(false),
+        }
+    }
+
+    pub(crate) fn get(&self) -> bool {
+        #[cfg(feature = "poison")]
+        return self.failed.load(Ordering::Relaxed);
+        #[cfg(not(feature = "poison"))]
+        false
+    }
+
+    pub(crate) fn clear(&self) {
+        #[cfg(feature = "poison")]
+        self.failed.store(false, Ordering::Relaxed);
+    }
+
+    /// Called when the lock is released, `was_panicking` is the value of
+    /// [`panicking`] when it was taken.
+    #[cfg(feature = "poison")]
+    pub(crate) fn done(&self, was_panicking: bool) {
+        if !was_panicking && panicking() {
+            self.failed.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(feature = "poison")]
+pub(crate) fn panicking() -> bool {
+    std::thread::panicking()
+}
@@ -6,6 +6,8 @@ use crate::base_type::v1::*;
 use crate::isr::*;
 use crate::shim::*;
 use crate::units::*;
+#[cfg(feature = "async")]
+use crate::waker_list::*;
 
 unsafe impl<T: Sized + Send> Send for Queue<T> {}
 unsafe impl<T: Sized + Send> Sync for Queue<T> {}
@@ -17,6 +19,10 @@ pub struct SendError<T> {
 }
 
 impl<T> SendError<T> {
+    pub(crate) fn new(err: FreeRtosError, item: T) -> Self {
+        Self { err, item }
+    }
+
     pub fn error(&self) -> FreeRtosError {
         self.err
     }
@@ -27,9 +33,18 @@ impl<T> SendError<T> {
 }
 
 /// A queue with a finite size.
+///
+/// With the `async` feature, `send_async` and `receive_async` wait without blocking
+/// the task. They are woken by the other methods of the same `Queue`, so blocking and
+/// async users can be mixed. Items sent by C code or through another `Queue` made
+/// with [`from_raw_handle`](Queue::from_raw_handle) don't wake them.
 #[derive(Debug)]
 pub struct Queue<T: Sized + Send> {
     queue: FreeRtosQueueHandle,
+    #[cfg(feature = "async")]
+    receivers: SharedWakerList,
+    #[cfg(feature = "async")]
+    senders: WakerList,
     item_type: PhantomData<T>,
 }
 
@@ -43,10 +58,7 @@ impl<T: Sized + Send> Queue<T> {
             return Err(FreeRtosError::OutOfMemory);
         }
 
-        Ok(Queue {
-            queue: handle,
-            item_type: PhantomData,
-        })
+        Ok(unsafe { Queue::from_raw_handle(handle) })
     }
 
     /// # Safety
@@ -58,6 +70,10 @@ impl<T: Sized + Send> Queue<T> {
     pub unsafe fn from_raw_handle(handle: FreeRtosQueueHandle) -> Self {
         Self {
             queue: handle,
+            #[cfg(feature = "async")]
+            receivers: SharedWakerList::new(),
+            #[cfg(feature = "async")]
+            senders: WakerList::new(),
             item_type: PhantomData,
         }
     }
@@ -78,6 +94,8 @@ impl<T: Sized + Send> Queue<T> {
                     item: ManuallyDrop::into_inner(item),
                 })
             } else {
+                #[cfg(feature = "async")]
+                self.receivers.wake_all();
                 Ok(())
             }
         }
@@ -99,6 +117,8 @@ impl<T: Sized + Send> Queue<T> {
                     item: ManuallyDrop::into_inner(item),
                 })
             } else {
+                #[cfg(feature = "async")]
+                self.receivers.wake_all_from_isr(context);
                 Ok(())
             }
         }
@@ -116,6 +136,8 @@ impl<T: Sized + Send> Queue<T> {
                 max_wait.to_ticks(),
             );
             if r == 0 {
+                #[cfg(feature = "async")]
+                self.senders.wake_all();
                 return Ok(buff.assume_init());
             } else {
                 return Err(FreeRtosError::QueueReceiveTimeout);
@@ -123,6 +145,34 @@ impl<T: Sized + Send> Queue<T> {
         }
     }
 
+    /// Send an item to the end of the queue, waiting for space without blocking the task.
+    ///
+    /// The item is dropped if the future is dropped before it completes.
+    #[cfg(feature = "async")]
+    pub async fn send_async(&self, item: T) {
+        let mut item = Some(item);
+        self.senders
+            .wait_for(|| match self.send(item.take()?, Duration::zero()) {
+                Ok(()) => Some(()),
+                Err(err) => {
+                    item = Some(err.into_item());
+                    None
+                }
+            })
+            .await
+    }
+
+    /// Wait for an item without blocking the task.
+    ///
+    /// Wakes from [`send_from_isr`](Queue::send_from_isr) are deferred to the timer
+    /// daemon task, see `use_timer_task` of `freertos-build`.
+    #[cfg(feature = "async")]
+    pub async fn receive_async(&self) -> T {
+        self.receivers
+            .wait_for(|| self.receive(Duration::zero()).ok())
+            .await
+    }
+
     /// Get the number of messages in the queue.
     pub fn len(&self) -> u32 {
         unsafe { freertos_rs_queue_messages_waiting(self.queue) }
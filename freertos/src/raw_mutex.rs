@@ -0,0 +1,278 @@
+//! Raw mutexes for the `mutex-traits`, `lock_api` and `embassy-sync` ecosystems.
+//!
+//! - [`FreeRtosRawMutex`] is a FreeRTOS mutex: waiting tasks block and
+//!   priority inheritance applies. It must not be used from an ISR.
+//! - [`FreeRtosCriticalRawMutex`] locks by entering a FreeRTOS critical section.
+//!   It never blocks and works in ISRs, but must only be held for a short time.
+//! - `FreeRtosRecursiveRawMutex` is a FreeRTOS recursive mutex for `embassy-sync`,
+//!   whose `lock` may be nested by the same task. It needs the `recursive-mutex` feature.
+
+#[cfg(all(feature = "embassy-sync", feature = "recursive-mutex"))]
+use crate::base::*;
+use crate::base_type::v1::*;
+use crate::isr::*;
+#[cfg(feature = "lock-api")]
+use crate::mutex::*;
+use crate::shim::*;
+#[cfg(all(feature = "embassy-sync", feature = "recursive-mutex"))]
+use crate::units::*;
+#[cfg(all(feature = "embassy-sync", feature = "recursive-mutex"))]
+use core::ffi::c_void;
+#[cfg(all(feature = "embassy-sync", feature = "recursive-mutex"))]
+use core::sync::atomic::AtomicPtr;
+use core::sync::atomic::{AtomicBool, Ordering};
+use mutex_traits::ConstInit;
+
+/// A raw mutex that locks by entering a FreeRTOS critical section.
+///
+/// The mutex is not reentrant, locking it twice from the same context panics.
+/// When locked from an ISR, nested locks have to be released in reverse order.
+pub struct FreeRtosCriticalRawMutex {
+    locked: AtomicBool,
+    restore_state: UnsafeCell<u32>,
+}
+
+// SAFETY: `restore_state` is only accessed by the holder of the lock.
+unsafe impl Send for FreeRtosCriticalRawMutex {}
+unsafe impl Sync for FreeRtosCriticalRawMutex {}
+
+impl FreeRtosCriticalRawMutex {
+    pub const fn new() -> Self {
+        Self {
+            locked: AtomicBool::new(false),
+            restore_state: UnsafeCell::new(0),
+        }
+    }
+
+    fn enter() -> u32 {
+        if is_in_isr() {
+            unsafe { freertos_rs_enter_critical_from_isr() }
+        } else {
+            unsafe { freertos_rs_enter_critical() };
+            0
+        }
+    }
+
+    fn exit(restore_state: u32) {
+        if is_in_isr() {
+            unsafe { freertos_rs_exit_critical_from_isr(restore_state) }
+        } else {
+            unsafe { freertos_rs_exit_critical() }
+        }
+    }
+
+    fn raw_try_lock(&self) -> bool {
+        let restore_state = Self::enter();
+        if self.locked.load(Ordering::Relaxed) {
+            Self::exit(restore_state);
+            return false;
+        }
+        self.locked.store(true, Ordering::Relaxed);
+        unsafe { *self.restore_state.get() = restore_state };
+        true
+    }
+
+    fn raw_lock(&self) {
+        // Nobody else can hold the lock while we are in the critical section.
+        if !self.raw_try_lock() {
+            panic!("Deadlocked");
+        }
+    }
+
+    unsafe fn raw_unlock(&self) {
+        let restore_state = unsafe { *self.restore_state.get() };
+        self.locked.store(false, Ordering::Relaxed);
+        Self::exit(restore_state);
+    }
+
+    fn raw_is_locked(&self) -> bool {
+        self.locked.load(Ordering::Relaxed)
+    }
+}
+
+impl Default for FreeRtosCriticalRawMutex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConstInit for FreeRtosCriticalRawMutex {
+    const INIT: Self = Self::new();
+}
+
+unsafe impl mutex_traits::RawMutex for FreeRtosCriticalRawMutex {
+    type GuardMarker = *mut ();
+
+    #[inline]
+    fn lock(&self) {
+        self.raw_lock()
+    }
+
+    #[inline]
+    fn try_lock(&self) -> bool {
+        self.raw_try_lock()
+    }
+
+    #[inline]
+    unsafe fn unlock(&self) {
+        unsafe { self.raw_unlock() }
+    }
+
+    #[inline]
+    fn is_locked(&self) -> bool {
+        self.raw_is_locked()
+    }
+}
+
+#[cfg(feature = "lock-api")]
+unsafe impl lock_api::RawMutex for FreeRtosCriticalRawMutex {
+    const INIT: Self = Self::new();
+    type GuardMarker = lock_api::GuardNoSend;
+
+    #[inline]
+    fn lock(&self) {
+        self.raw_lock()
+    }
+
+    #[inline]
+    fn try_lock(&self) -> bool {
+        self.raw_try_lock()
+    }
+
+    #[inline]
+    unsafe fn unlock(&self) {
+        unsafe { self.raw_unlock() }
+    }
+
+    #[inline]
+    fn is_locked(&self) -> bool {
+        self.raw_is_locked()
+    }
+}
+
+#[cfg(feature = "lock-api")]
+unsafe impl lock_api::RawMutex for FreeRtosRawMutex {
+    const INIT: Self = Self::empty();
+    type GuardMarker = lock_api::GuardNoSend;
+
+    #[inline]
+    fn lock(&self) {
+        // `portMAX_DELAY` is only infinite with `INCLUDE_vTaskSuspend`.
+        while !self.try_lock_block(true) {}
+    }
+
+    #[inline]
+    fn try_lock(&self) -> bool {
+        self.try_lock_block(false)
+    }
+
+    #[inline]
+    unsafe fn unlock(&self) {
+        unsafe { <Self as mutex_traits::RawMutex>::unlock(self) }
+    }
+
+    #[inline]
+    fn is_locked(&self) -> bool {
+        <Self as mutex_traits::RawMutex>::is_locked(self)
+    }
+}
+
+#[cfg(feature = "embassy-sync")]
+unsafe impl embassy_sync::blocking_mutex::raw::RawMutex for FreeRtosCriticalRawMutex {
+    const INIT: Self = Self::new();
+
+    fn lock<R>(&self, f: impl FnOnce() -> R) -> R {
+        struct Exit(u32);
+        impl Drop for Exit {
+            fn drop(&mut self) {
+                FreeRtosCriticalRawMutex::exit(self.0);
+            }
+        }
+
+        // embassy-sync only hands out shared references, so nesting is fine here.
+        let _exit = Exit(Self::enter());
+        f()
+    }
+}
+
+/// A FreeRTOS recursive mutex for `embassy-sync`.
+///
+/// `embassy_sync::blocking_mutex::raw::RawMutex::lock` may be nested by the same task,
+/// so [`FreeRtosRawMutex`](crate::FreeRtosRawMutex) can't implement it. Waiting tasks block and priority
+/// inheritance applies, it must not be used from an ISR.
+#[cfg(all(feature = "embassy-sync", feature = "recursive-mutex"))]
+pub struct FreeRtosRecursiveRawMutex {
+    /// Created on first use.
+    handle: AtomicPtr<c_void>,
+}
+
+#[cfg(all(feature = "embassy-sync", feature = "recursive-mutex"))]
+impl FreeRtosRecursiveRawMutex {
+    pub const fn new() -> Self {
+        Self {
+            handle: AtomicPtr::new(core::ptr::null_mut()),
+        }
+    }
+
+    fn handle(&self) -> FreeRtosSemaphoreHandle {
+        let handle = self.handle.load(Ordering::Acquire);
+        if !handle.is_null() {
+            return handle;
+        }
+
+        let created = unsafe { freertos_rs_create_recursive_mutex() } as *mut c_void;
+        if created.is_null() {
+            panic!("no memory for the mutex");
+        }
+        match self.handle.compare_exchange(
+            core::ptr::null_mut(),
+            created,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            Ok(_) => created,
+            Err(existing) => {
+                unsafe { freertos_rs_delete_semaphore(created) };
+                existing
+            }
+        }
+    }
+}
+
+#[cfg(all(feature = "embassy-sync", feature = "recursive-mutex"))]
+impl Default for FreeRtosRecursiveRawMutex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(feature = "embassy-sync", feature = "recursive-mutex"))]
+impl Drop for FreeRtosRecursiveRawMutex {
+    fn drop(&mut self) {
+        let handle = *self.handle.get_mut();
+        if !handle.is_null() {
+            unsafe { freertos_rs_delete_semaphore(handle) }
+        }
+    }
+}
+
+#[cfg(all(feature = "embassy-sync", feature = "recursive-mutex"))]
+unsafe impl embassy_sync::blocking_mutex::raw::RawMutex for FreeRtosRecursiveRawMutex {
+    const INIT: Self = Self::new();
+
+    fn lock<R>(&self, f: impl FnOnce() -> R) -> R {
+        struct Give(FreeRtosSemaphoreHandle);
+        impl Drop for Give {
+            fn drop(&mut self) {
+                unsafe { freertos_rs_give_recursive_semaphore(self.0) };
+            }
+        }
+
+        let handle = self.handle();
+        // `portMAX_DELAY` is only infinite with `INCLUDE_vTaskSuspend`.
+        let max_wait = FreeRtosTimeUnitsShimmed::get_max_wait();
+        while unsafe { freertos_rs_take_recursive_semaphore(handle, max_wait) } != 0 {}
+        let _give = Give(handle);
+        f()
+    }
+}
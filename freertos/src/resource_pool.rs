@@ -0,0 +1,170 @@
+use crate::base::*;
+use crate::base_type::v1::*;
+use crate::critical::*;
+use crate::shim::*;
+use crate::units::*;
+use core::ffi::c_void;
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+unsafe impl<T: Send, const N: usize> Send for ResourcePool<T, N> {}
+unsafe impl<T: Send, const N: usize> Sync for ResourcePool<T, N> {}
+
+/// A pool of `N` interchangeable resources, such as DMA channels or buffers,
+/// guarded by a counting semaphore.
+///
+/// The resources are stored inline and the semaphore is created on first use,
+/// so the pool can be placed in a `static` as well as behind an `Arc` or `Box`.
+///
+/// ```ignore
+/// static BUFFERS: ResourcePool<[u8; 64], 4> = ResourcePool::new([[0; 64]; 4]);
+///
+/// let mut buf = BUFFERS.acquire(Duration::ms(10))?;
+/// buf[0] = 0xAA;
+/// // the buffer goes back to the pool when `buf` is dropped
+/// ```
+pub struct ResourcePool<T, const N: usize> {
+    semaphore: AtomicPtr<c_void>,
+    in_use: UnsafeCell<[bool; N]>,
+    resources: UnsafeCell<[T; N]>,
+}
+
+impl<T, const N: usize> ResourcePool<T, N> {
+    /// Create a new pool owning the given resources.
+    pub const fn new(resources: [T; N]) -> Self {
+        const { assert!(N > 0, "a resource pool needs at least one resource") };
+        Self {
+            semaphore: AtomicPtr::new(core::ptr::null_mut()),
+            in_use: UnsafeCell::new([false; N]),
+            resources: UnsafeCell::new(resources),
+        }
+    }
+
+    /// Create the underlying counting semaphore now instead of on the first [`acquire`].
+    ///
+    /// [`acquire`]: ResourcePool::acquire
+    pub fn init(&self) -> Result<(), FreeRtosError> {
+        self.semaphore().map(|_| ())
+    }
+
+    fn semaphore(&self) -> Result<FreeRtosSemaphoreHandle, FreeRtosError> {
+        let semaphore = self.semaphore.load(Ordering::Acquire);
+        if !semaphore.is_null() {
+            return Ok(semaphore);
+        }
+
+        let created = unsafe { freertos_rs_create_counting_semaphore(N as u32, N as u32) };
+        if created.is_null() {
+            return Err(FreeRtosError::OutOfMemory);
+        }
+        // Another task may have created one in the meantime, keep only the first.
+        match self.semaphore.compare_exchange(
+            core::ptr::null_mut(),
+            created as *mut c_void,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            Ok(_) => Ok(created),
+            Err(existing) => {
+                unsafe { freertos_rs_delete_semaphore(created) };
+                Ok(existing)
+            }
+        }
+    }
+
+    /// Wait for a free resource and take it out of the pool until the guard is dropped.
+    pub fn acquire<D: DurationTicks>(
+        &self,
+        max_wait: D,
+    ) -> Result<ResourceGuard<'_, T, N>, FreeRtosError> {
+        let semaphore = self.semaphore()?;
+        if unsafe { freertos_rs_take_semaphore(semaphore, max_wait.to_ticks()) } != 0 {
+            return Err(FreeRtosError::Timeout);
+        }
+
+        // Holding a token guarantees that at least one slot is free.
+        let index = {
+            let _lock = CriticalRegion::enter();
+            let in_use = unsafe { &mut *self.in_use.get() };
+            let index = in_use.iter().position(|used| !*used).unwrap();
+            in_use[index] = true;
+            index
+        };
+
+        Ok(ResourceGuard { pool: self, index })
+    }
+
+    /// Number of resources that are currently free.
+    pub fn available(&self) -> usize {
+        let semaphore = self.semaphore.load(Ordering::Acquire);
+        if semaphore.is_null() {
+            N
+        } else {
+            unsafe { freertos_rs_get_semaphore_count(semaphore) as usize }
+        }
+    }
+
+    /// Total number of resources in the pool.
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Get mutable access to all resources.
+    ///
+    /// No locking is needed because the mutable reference guarantees that no guard is alive.
+    pub fn get_mut(&mut self) -> &mut [T; N] {
+        self.resources.get_mut()
+    }
+}
+
+impl<T, const N: usize> Drop for ResourcePool<T, N> {
+    fn drop(&mut self) {
+        let semaphore = *self.semaphore.get_mut();
+        if !semaphore.is_null() {
+            unsafe { freertos_rs_delete_semaphore(semaphore) }
+        }
+    }
+}
+
+/// Exclusive access to one resource of a [`ResourcePool`], returned to the pool on drop.
+pub struct ResourceGuard<'a, T, const N: usize> {
+    pool: &'a ResourcePool<T, N>,
+    index: usize,
+}
+
+impl<'a, T, const N: usize> ResourceGuard<'a, T, N> {
+    /// Index of the resource inside the pool.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    fn as_ptr(&self) -> *mut T {
+        unsafe { (self.pool.resources.get() as *mut T).add(self.index) }
+    }
+}
+
+impl<'a, T, const N: usize> Deref for ResourceGuard<'a, T, N> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.as_ptr() }
+    }
+}
+
+impl<'a, T, const N: usize> DerefMut for ResourceGuard<'a, T, N> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.as_ptr() }
+    }
+}
+
+impl<'a, T, const N: usize> Drop for ResourceGuard<'a, T, N> {
+    fn drop(&mut self) {
+        {
+            let _lock = CriticalRegion::enter();
+            let in_use = unsafe { &mut *self.pool.in_use.get() };
+            in_use[self.index] = false;
+        }
+        unsafe {
+            freertos_rs_give_semaphore(self.pool.semaphore.load(Ordering::Acquire));
+        }
+    }
+}
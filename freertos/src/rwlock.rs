@@ -0,0 +1,168 @@
+use crate::base::*;
+use crate::base_type::v1::*;
+use crate::instant::*;
+use crate::mutex::*;
+use crate::semaphore::*;
+use crate::units::*;
+
+/// Default number of readers that can hold a [`RwLock`] at the same time.
+pub const RWLOCK_DEFAULT_MAX_READERS: u32 = 8;
+
+unsafe impl<T: ?Sized + Send> Send for RwLock<T> {}
+unsafe impl<T: ?Sized + Send + Sync> Sync for RwLock<T> {}
+
+/// A reader-writer lock, allowing many readers or one writer at a time.
+///
+/// Every reader holds one token of a counting semaphore, a writer takes all of them.
+/// Both pass through a mutex first: a waiting writer keeps that mutex while it collects
+/// the tokens, so new readers queue up behind it and writers can't be starved.
+/// Because the mutex uses priority inheritance, tasks blocked behind a writer
+/// raise its priority, readers that already hold the lock are not boosted.
+///
+/// The lock is not reentrant: a task that calls [`write`](RwLock::write) while it still
+/// holds a read guard of the same lock waits for its own token and deadlocks, or fails
+/// with a timeout.
+pub struct RwLock<T: ?Sized> {
+    writer: MutexNormal,
+    readers: Semaphore,
+    max_readers: u32,
+    data: UnsafeCell<T>,
+}
+
+impl<T> RwLock<T> {
+    /// Create a new lock allowing [`RWLOCK_DEFAULT_MAX_READERS`] concurrent readers.
+    pub fn new(value: T) -> Result<Self, FreeRtosError> {
+        Self::with_max_readers(value, RWLOCK_DEFAULT_MAX_READERS)
+    }
+
+    /// Create a new lock allowing `max_readers` concurrent readers.
+    ///
+    /// A writer has to take one token per reader, so keep this number small.
+    pub fn with_max_readers(value: T, max_readers: u32) -> Result<Self, FreeRtosError> {
+        Ok(Self {
+            writer: MutexNormal::create()?,
+            readers: Semaphore::new_counting(max_readers, max_readers)?,
+            max_readers,
+            data: UnsafeCell::new(value),
+        })
+    }
+
+    /// Consume the lock and return its inner value
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+}
+
+impl<T: ?Sized> RwLock<T> {
+    /// Wait for shared read access.
+    ///
+    /// Blocks while a writer holds or waits for the lock, or all reader slots are taken.
+    pub fn read<D: DurationTicks>(
+        &self,
+        max_wait: D,
+    ) -> Result<RwLockReadGuard<'_, T>, FreeRtosError> {
+        let budget = Deadline::after(max_wait);
+        self.writer.take(budget)?;
+        let res = self.readers.take(budget);
+        self.writer.give();
+        res.map(|()| RwLockReadGuard {
+            lock: self,
+            _not_send: PhantomData,
+        })
+    }
+
+    /// Wait for exclusive write access.
+    pub fn write<D: DurationTicks>(
+        &self,
+        max_wait: D,
+    ) -> Result<RwLockWriteGuard<'_, T>, FreeRtosError> {
+        let budget = Deadline::after(max_wait);
+        self.writer.take(budget)?;
+
+        for taken in 0..self.max_readers {
+            if let Err(e) = self.readers.take(budget) {
+                for _ in 0..taken {
+                    self.readers.give();
+                }
+                self.writer.give();
+                return Err(e);
+            }
+        }
+
+        Ok(RwLockWriteGuard {
+            lock: self,
+            _not_send: PhantomData,
+        })
+    }
+
+    /// Get mutable reference to inner value.
+    ///
+    /// This method does not lock because mutable reference guarantees exclusive access.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.data.get_mut()
+    }
+}
+
+/// Shared read access to the value of a [`RwLock`], released on drop.
+///
+/// Like [`MutexGuard`] it can't be sent to another task, the lock has to be released
+/// by the task that took it.
+///
+/// ```compile_fail
+/// fn assert_send<T: Send>() {}
+/// assert_send::<freertos_next::RwLockReadGuard<'static, u32>>();
+/// ```
+pub struct RwLockReadGuard<'a, T: ?Sized + 'a> {
+    lock: &'a RwLock<T>,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl<'a, T: ?Sized> Deref for RwLockReadGuard<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<'a, T: ?Sized> Drop for RwLockReadGuard<'a, T> {
+    fn drop(&mut self) {
+        self.lock.readers.give();
+    }
+}
+
+/// Exclusive write access to the value of a [`RwLock`], released on drop.
+///
+/// It can't be sent to another task, only the task holding the writer mutex can give it back.
+///
+/// ```compile_fail
+/// fn assert_send<T: Send>() {}
+/// assert_send::<freertos_next::RwLockWriteGuard<'static, u32>>();
+/// ```
+pub struct RwLockWriteGuard<'a, T: ?Sized + 'a> {
+    lock: &'a RwLock<T>,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl<'a, T: ?Sized> Deref for RwLockWriteGuard<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<'a, T: ?Sized> DerefMut for RwLockWriteGuard<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl<'a, T: ?Sized> Drop for RwLockWriteGuard<'a, T> {
+    fn drop(&mut self) {
+        for _ in 0..self.lock.max_readers {
+            self.lock.readers.give();
+        }
+        self.lock.writer.give();
+    }
+}
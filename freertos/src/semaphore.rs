@@ -2,10 +2,18 @@ use crate::base::*;
 use crate::isr::*;
 use crate::shim::*;
 use crate::units::*;
+#[cfg(feature = "async")]
+use crate::waker_list::*;
 
 /// A counting or binary semaphore
+///
+/// With the `async` feature, `take_async` waits without blocking the task. It is
+/// woken by [`give`](Semaphore::give) and [`give_from_isr`](Semaphore::give_from_isr)
+/// of the same `Semaphore`, not by C code giving the raw handle.
 pub struct Semaphore {
     semaphore: FreeRtosSemaphoreHandle,
+    #[cfg(feature = "async")]
+    wakers: SharedWakerList,
 }
 
 unsafe impl Send for Semaphore {}
@@ -19,7 +27,7 @@ impl Semaphore {
             if s == 0 as *const _ {
                 return Err(FreeRtosError::OutOfMemory);
             }
-            Ok(Semaphore { semaphore: s })
+            Ok(Semaphore::from_raw_handle(s))
         }
     }
 
@@ -31,7 +39,7 @@ impl Semaphore {
             if s == 0 as *const _ {
                 return Err(FreeRtosError::OutOfMemory);
             }
-            Ok(Semaphore { semaphore: s })
+            Ok(Semaphore::from_raw_handle(s))
         }
     }
 
@@ -43,7 +51,11 @@ impl Semaphore {
     /// To create mutex from raw handle use [`crate::mutex::MutexInnerImpl::from_raw_handle`].
     #[inline]
     pub unsafe fn from_raw_handle(handle: FreeRtosSemaphoreHandle) -> Self {
-        Self { semaphore: handle }
+        Self {
+            semaphore: handle,
+            #[cfg(feature = "async")]
+            wakers: SharedWakerList::new(),
+        }
     }
     #[inline]
     pub fn raw_handle(&self) -> FreeRtosSemaphoreHandle {
@@ -57,7 +69,12 @@ impl Semaphore {
 
     /// Returns `true` on success, `false` when semaphore count already reached its limit
     pub fn give(&self) -> bool {
-        unsafe { freertos_rs_give_semaphore(self.semaphore) == 0 }
+        let given = unsafe { freertos_rs_give_semaphore(self.semaphore) == 0 };
+        #[cfg(feature = "async")]
+        if given {
+            self.wakers.wake_all();
+        }
+        given
     }
 
     pub fn take<D: DurationTicks>(&self, max_wait: D) -> Result<(), FreeRtosError> {
@@ -72,9 +89,34 @@ impl Semaphore {
         }
     }
 
+    /// Wait for the semaphore without blocking the task.
+    ///
+    /// Wakes from [`give_from_isr`](Semaphore::give_from_isr) are deferred to the timer
+    /// daemon task, see `use_timer_task` of `freertos-build`.
+    #[cfg(feature = "async")]
+    pub async fn take_async(&self) {
+        self.wakers
+            .wait_for(|| self.take(Duration::zero()).ok())
+            .await
+    }
+
+    /// Get the current count of the semaphore.
+    ///
+    /// For a binary semaphore this is `1` if it is available and `0` otherwise.
+    pub fn count(&self) -> u32 {
+        unsafe { freertos_rs_get_semaphore_count(self.semaphore) }
+    }
+
     /// Returns `true` on success, `false` when semaphore count already reached its limit
     pub fn give_from_isr(&self, context: &mut InterruptContext) -> bool {
-        unsafe { freertos_rs_give_semaphore_isr(self.semaphore, context.get_task_field_mut()) == 0 }
+        let given = unsafe {
+            freertos_rs_give_semaphore_isr(self.semaphore, context.get_task_field_mut()) == 0
+        };
+        #[cfg(feature = "async")]
+        if given {
+            self.wakers.wake_all_from_isr(context);
+        }
+        given
     }
 
     /// Returns `true` on success, `false` if the semaphore was not successfully taken because it was not available
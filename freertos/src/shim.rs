@@ -11,22 +11,31 @@ unsafe extern "C" {
 
     pub fn freertos_rs_sizeof(_type: u8) -> u8;
 
-    pub fn freertos_rs_vTaskDelayUntil(
+    pub fn freertos_rs_xTaskDelayUntil(
         pxPreviousWakeTime: *mut FreeRtosTickType,
         xTimeIncrement: FreeRtosTickType,
-    );
+    ) -> FreeRtosBaseType;
     pub fn freertos_rs_vTaskDelay(xTicksToDelay: FreeRtosTickType);
     #[cfg(feature = "cpu-clock")]
     pub fn freertos_rs_get_configCPU_CLOCK_HZ() -> FreeRtosUnsignedLong;
     pub fn freertos_rs_get_portTICK_PERIOD_MS() -> FreeRtosTickType;
+    pub fn freertos_rs_get_configTICK_RATE_HZ() -> FreeRtosTickType;
 
     pub fn freertos_rs_get_number_of_tasks() -> FreeRtosUBaseType;
 
     pub fn freertos_rs_xTaskGetTickCount() -> FreeRtosTickType;
     pub fn freertos_rs_xTaskGetTickCountFromISR() -> FreeRtosTickType;
+    pub fn freertos_rs_get_tick_count64() -> u64;
+    pub fn freertos_rs_get_tick_count64_from_isr() -> u64;
+    #[cfg(hosted)]
+    pub fn freertos_rs_host_monotonic_us() -> u64;
 
     pub fn freertos_rs_create_recursive_mutex() -> FreeRtosSemaphoreHandle;
     pub fn freertos_rs_create_mutex() -> FreeRtosSemaphoreHandle;
+    pub fn freertos_rs_create_mutex_static(
+        buffer: FreeRtosMutVoidPtr,
+        size: usize,
+    ) -> FreeRtosSemaphoreHandle;
 
     pub fn freertos_rs_take_recursive_semaphore(
         semaphore: FreeRtosSemaphoreHandle,
@@ -50,7 +59,11 @@ unsafe extern "C" {
         xHigherPriorityTaskWoken: FreeRtosBaseTypeMutPtr,
     ) -> FreeRtosBaseType;
 
+    pub fn freertos_rs_get_mutex_holder(mutex: FreeRtosSemaphoreHandle) -> FreeRtosTaskHandle;
+
     pub fn freertos_rs_delete_semaphore(semaphore: FreeRtosSemaphoreHandle);
+    pub fn freertos_rs_get_semaphore_count(semaphore: FreeRtosSemaphoreHandle)
+    -> FreeRtosUBaseType;
 
     pub fn freertos_rs_create_binary_semaphore() -> FreeRtosSemaphoreHandle;
     pub fn freertos_rs_create_counting_semaphore(
@@ -96,6 +109,8 @@ unsafe extern "C" {
         value: u32,
         action: u8,
     ) -> FreeRtosBaseType;
+    pub fn freertos_rs_sync_notify_give(task: FreeRtosTaskHandle);
+    pub fn freertos_rs_sync_notify_take(wait: FreeRtosTickType) -> u32;
     pub fn freertos_rs_task_notify_isr(
         task: FreeRtosTaskHandle,
         value: u32,
@@ -127,6 +142,9 @@ unsafe extern "C" {
     pub fn freertos_rs_get_stack_high_water_mark(task: FreeRtosTaskHandle) -> FreeRtosBaseType;
 
     pub fn freertos_rs_get_current_task() -> FreeRtosTaskHandle;
+    pub fn freertos_rs_task_get_priority(task: FreeRtosTaskHandle) -> FreeRtosUBaseType;
+    pub fn freertos_rs_task_get_base_priority(task: FreeRtosTaskHandle) -> FreeRtosUBaseType;
+    pub fn freertos_rs_task_set_priority(task: FreeRtosTaskHandle, priority: FreeRtosUBaseType);
     pub fn freertos_rs_get_system_state(
         tasks: *mut FreeRtosTaskStatusFfi,
         tasks_len: FreeRtosUBaseType,
@@ -137,12 +155,20 @@ unsafe extern "C" {
 
     pub fn freertos_rs_timer_create(
         name: FreeRtosCharPtr,
-        name_len: u8,
         period: FreeRtosTickType,
         auto_reload: u8,
         timer_id: FreeRtosVoidPtr,
         callback: extern "C" fn(FreeRtosTimerHandle) -> (),
     ) -> FreeRtosTimerHandle;
+    pub fn freertos_rs_timer_create_static(
+        name: FreeRtosCharPtr,
+        period: FreeRtosTickType,
+        auto_reload: u8,
+        timer_id: FreeRtosVoidPtr,
+        callback: extern "C" fn(FreeRtosTimerHandle) -> (),
+        buffer: FreeRtosMutVoidPtr,
+        size: usize,
+    ) -> FreeRtosTimerHandle;
     pub fn freertos_rs_timer_start(
         timer: FreeRtosTimerHandle,
         block_time: FreeRtosTickType,
@@ -165,6 +191,41 @@ unsafe extern "C" {
         new_period: FreeRtosTickType,
     ) -> FreeRtosBaseType;
     pub fn freertos_rs_timer_get_id(timer: FreeRtosTimerHandle) -> FreeRtosVoidPtr;
+    pub fn freertos_rs_timer_reset(
+        timer: FreeRtosTimerHandle,
+        block_time: FreeRtosTickType,
+    ) -> FreeRtosBaseType;
+    pub fn freertos_rs_timer_reset_from_isr(
+        timer: FreeRtosTimerHandle,
+        xHigherPriorityTaskWoken: FreeRtosBaseTypeMutPtr,
+    ) -> FreeRtosBaseType;
+    pub fn freertos_rs_timer_stop_from_isr(
+        timer: FreeRtosTimerHandle,
+        xHigherPriorityTaskWoken: FreeRtosBaseTypeMutPtr,
+    ) -> FreeRtosBaseType;
+    pub fn freertos_rs_timer_change_period_from_isr(
+        timer: FreeRtosTimerHandle,
+        new_period: FreeRtosTickType,
+        xHigherPriorityTaskWoken: FreeRtosBaseTypeMutPtr,
+    ) -> FreeRtosBaseType;
+    pub fn freertos_rs_timer_is_active(timer: FreeRtosTimerHandle) -> FreeRtosBaseType;
+    pub fn freertos_rs_timer_get_period(timer: FreeRtosTimerHandle) -> FreeRtosTickType;
+    pub fn freertos_rs_timer_get_expiry_time(timer: FreeRtosTimerHandle) -> FreeRtosTickType;
+    pub fn freertos_rs_timer_set_reload_mode(timer: FreeRtosTimerHandle, auto_reload: u8);
+    pub fn freertos_rs_timer_get_reload_mode(timer: FreeRtosTimerHandle) -> u8;
+    pub fn freertos_rs_timer_get_name(timer: FreeRtosTimerHandle) -> FreeRtosCharPtr;
+    pub fn freertos_rs_timer_pend_function_call(
+        function: extern "C" fn(FreeRtosMutVoidPtr, u32),
+        parameter1: FreeRtosMutVoidPtr,
+        parameter2: u32,
+        block_time: FreeRtosTickType,
+    ) -> FreeRtosBaseType;
+    pub fn freertos_rs_timer_pend_function_call_isr(
+        function: extern "C" fn(FreeRtosMutVoidPtr, u32),
+        parameter1: FreeRtosMutVoidPtr,
+        parameter2: u32,
+        xHigherPriorityTaskWoken: FreeRtosBaseTypeMutPtr,
+    ) -> FreeRtosBaseType;
 
     pub fn freertos_rs_is_in_isr() -> FreeRtosBaseType;
     pub fn freertos_rs_enter_critical();
@@ -198,6 +259,18 @@ unsafe extern "C" {
         bits_to_wait_for: FreeRtosEventBitsType,
         ticks_to_wait: FreeRtosTickType,
     ) -> FreeRtosEventBitsType;
+    pub fn freertos_rs_event_group_get_bits_isr(
+        event_group: FreeRtosEventGroupHandle,
+    ) -> FreeRtosEventBitsType;
+    pub fn freertos_rs_event_group_set_bits_isr(
+        event_group: FreeRtosEventGroupHandle,
+        bits_to_set: FreeRtosEventBitsType,
+        xHigherPriorityTaskWoken: FreeRtosBaseTypeMutPtr,
+    ) -> FreeRtosBaseType;
+    pub fn freertos_rs_event_group_clear_bits_isr(
+        event_group: FreeRtosEventGroupHandle,
+        bits_to_clear: FreeRtosEventBitsType,
+    ) -> FreeRtosBaseType;
 
     pub fn freertos_rs_get_free_heap_size() -> usize;
     pub fn freertos_rs_get_minimum_free_heap_size() -> usize;
@@ -0,0 +1,152 @@
+use crate::base::*;
+use core::cell::Cell;
+
+/// Number of hooks that [`SLEEP_HOOKS`] can hold.
+pub const SLEEP_HOOK_SLOTS: usize = 4;
+
+/// Code that runs around tickless idle, see `tickless_idle` of `freertos-build`.
+///
+/// All methods get the number of ticks the kernel expects to stay idle.
+pub trait SleepHook: Sync {
+    /// Whether the idle task may stop the tick. Runs with the scheduler suspended,
+    /// returning `false` keeps the tick running for this idle period.
+    fn allow_sleep(&self, _expected_idle_ticks: FreeRtosTickType) -> bool {
+        true
+    }
+
+    /// Runs with interrupts disabled right before the core sleeps, for example to
+    /// gate peripheral clocks.
+    fn pre_sleep(&self, _expected_idle_ticks: FreeRtosTickType) {}
+
+    /// Runs with interrupts disabled after the core woke up, to undo [`pre_sleep`](SleepHook::pre_sleep).
+    fn post_sleep(&self, _expected_idle_ticks: FreeRtosTickType) {}
+}
+
+type Hooks = [Option<&'static dyn SleepHook>; SLEEP_HOOK_SLOTS];
+
+/// The registry of [`SleepHook`]s, called by the kernel through `configPRE_SUPPRESS_TICKS_AND_SLEEP_PROCESSING`,
+/// `configPRE_SLEEP_PROCESSING` and `configPOST_SLEEP_PROCESSING`.
+///
+/// Hooks are asked in registration order before sleeping and called in reverse order
+/// after waking up. Ports without tickless idle, such as POSIX, never call them, use
+/// [`simulate`](SleepHooks::simulate) to test hooks there.
+///
+/// ```ignore
+/// struct Uart;
+///
+/// impl SleepHook for Uart {
+///     fn allow_sleep(&self, _: FreeRtosTickType) -> bool {
+///         !UART.is_transmitting()
+///     }
+///     fn pre_sleep(&self, _: FreeRtosTickType) {
+///         UART.disable_clock();
+///     }
+///     fn post_sleep(&self, _: FreeRtosTickType) {
+///         UART.enable_clock();
+///     }
+/// }
+///
+/// SLEEP_HOOKS.register(&Uart)?;
+/// ```
+pub struct SleepHooks {
+    hooks: critical_section::Mutex<Cell<Hooks>>,
+}
+
+pub static SLEEP_HOOKS: SleepHooks = SleepHooks {
+    hooks: critical_section::Mutex::new(Cell::new([None; SLEEP_HOOK_SLOTS])),
+};
+
+impl SleepHooks {
+    /// Add a hook, fails with [`FreeRtosError::OutOfMemory`] if all
+    /// [`SLEEP_HOOK_SLOTS`] are taken.
+    pub fn register(&self, hook: &'static dyn SleepHook) -> Result<(), FreeRtosError> {
+        critical_section::with(|cs| {
+            let cell = self.hooks.borrow(cs);
+            let mut hooks = cell.get();
+            let slot = hooks
+                .iter_mut()
+                .find(|slot| slot.is_none())
+                .ok_or(FreeRtosError::OutOfMemory)?;
+            *slot = Some(hook);
+            cell.set(hooks);
+            Ok(())
+        })
+    }
+
+    /// Remove a hook, returns `false` if it wasn't registered.
+    pub fn unregister(&self, hook: &'static dyn SleepHook) -> bool {
+        critical_section::with(|cs| {
+            let cell = self.hooks.borrow(cs);
+            let mut hooks = cell.get();
+            let Some(index) = hooks
+                .iter()
+                .position(|slot| slot.is_some_and(|h| core::ptr::addr_eq(h, hook)))
+            else {
+                return false;
+            };
+            // Keep the hooks in registration order without gaps.
+            hooks[index..].rotate_left(1);
+            hooks[SLEEP_HOOK_SLOTS - 1] = None;
+            cell.set(hooks);
+            true
+        })
+    }
+
+    /// Run the hooks the way a tickless idle period does and return whether the core
+    /// would have slept.
+    pub fn simulate(&self, expected_idle_ticks: FreeRtosTickType) -> bool {
+        if !self.allow_sleep(expected_idle_ticks) {
+            return false;
+        }
+        self.pre_sleep(expected_idle_ticks);
+        self.post_sleep(expected_idle_ticks);
+        true
+    }
+
+    fn hooks(&self) -> Hooks {
+        critical_section::with(|cs| self.hooks.borrow(cs).get())
+    }
+
+    fn allow_sleep(&self, expected_idle_ticks: FreeRtosTickType) -> bool {
+        self.hooks()
+            .iter()
+            .flatten()
+            .all(|hook| hook.allow_sleep(expected_idle_ticks))
+    }
+
+    fn pre_sleep(&self, expected_idle_ticks: FreeRtosTickType) {
+        for hook in self.hooks().iter().flatten() {
+            hook.pre_sleep(expected_idle_ticks);
+        }
+    }
+
+    fn post_sleep(&self, expected_idle_ticks: FreeRtosTickType) {
+        for hook in self.hooks().iter().rev().flatten() {
+            hook.post_sleep(expected_idle_ticks);
+        }
+    }
+}
+
+/// `configPRE_SUPPRESS_TICKS_AND_SLEEP_PROCESSING`, 0 keeps the tick running.
+#[unsafe(no_mangle)]
+pub extern "C" fn freertos_rs_pre_suppress_ticks_and_sleep(
+    expected_idle_ticks: FreeRtosTickType,
+) -> FreeRtosTickType {
+    if SLEEP_HOOKS.allow_sleep(expected_idle_ticks) {
+        expected_idle_ticks
+    } else {
+        0
+    }
+}
+
+/// `configPRE_SLEEP_PROCESSING`
+#[unsafe(no_mangle)]
+pub extern "C" fn freertos_rs_pre_sleep_processing(expected_idle_ticks: FreeRtosTickType) {
+    SLEEP_HOOKS.pre_sleep(expected_idle_ticks);
+}
+
+/// `configPOST_SLEEP_PROCESSING`
+#[unsafe(no_mangle)]
+pub extern "C" fn freertos_rs_post_sleep_processing(expected_idle_ticks: FreeRtosTickType) {
+    SLEEP_HOOKS.post_sleep(expected_idle_ticks);
+}
@@ -0,0 +1,95 @@
+use crate::base::*;
+use crate::base_type::v1::*;
+use crate::mutex::*;
+#[cfg(feature = "poison")]
+use crate::poison::*;
+use crate::shim::*;
+use crate::units::*;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// Number of words reserved for the kernel's `StaticSemaphore_t`.
+///
+/// The shim asserts that the structure of the configured port fits.
+pub const STATIC_SEMAPHORE_WORDS: usize = 32;
+
+#[repr(C)]
+struct StaticSemaphoreBuffer([usize; STATIC_SEMAPHORE_WORDS]);
+
+unsafe impl<T: Send> Send for StaticMutex<T> {}
+unsafe impl<T: Send> Sync for StaticMutex<T> {}
+
+/// A [`Mutex`] with its kernel object stored inline, for use in `static` items.
+///
+/// The kernel mutex is created with `xSemaphoreCreateMutexStatic` on first use.
+/// That can't fail, so unlike [`Mutex::new`] neither construction nor locking runs out of memory.
+/// Requires the `static-allocation` feature, which enables `configSUPPORT_STATIC_ALLOCATION`.
+///
+/// ```ignore
+/// static CONFIG: StaticMutex<Config> = StaticMutex::new(Config::DEFAULT);
+///
+/// let mut config = CONFIG.lock(Duration::ms(10))?;
+/// config.baud_rate = 115_200;
+/// ```
+pub struct StaticMutex<T> {
+    initialized: AtomicBool,
+    buffer: UnsafeCell<StaticSemaphoreBuffer>,
+    mutex: UnsafeCell<Mutex<T>>,
+}
+
+impl<T> StaticMutex<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            initialized: AtomicBool::new(false),
+            buffer: UnsafeCell::new(StaticSemaphoreBuffer([0; STATIC_SEMAPHORE_WORDS])),
+            mutex: UnsafeCell::new(Mutex::from_parts(MutexNormal::empty(), value)),
+        }
+    }
+
+    /// Get the mutex, creating the kernel object on the first call.
+    ///
+    /// The kernel keeps pointers into the inline buffer, so the mutex must not move anymore
+    /// once it has been created. That is why this takes a `'static` reference.
+    pub fn get(&'static self) -> &'static Mutex<T> {
+        if !self.initialized.load(Ordering::Acquire) {
+            critical_section::with(|_| {
+                if !self.initialized.load(Ordering::Relaxed) {
+                    unsafe {
+                        let handle = freertos_rs_create_mutex_static(
+                            self.buffer.get() as FreeRtosMutVoidPtr,
+                            mem::size_of::<StaticSemaphoreBuffer>(),
+                        );
+                        *(*self.mutex.get()).inner_mutex_mut() =
+                            MutexNormal::from_raw_handle(handle);
+                    }
+                    self.initialized.store(true, Ordering::Release);
+                }
+            });
+        }
+        unsafe { &*self.mutex.get() }
+    }
+
+    /// Try to obtain a lock and mutable access to our inner value
+    pub fn lock<D: DurationTicks>(
+        &'static self,
+        max_wait: D,
+    ) -> Result<MutexGuard<'static, T, MutexNormal>, FreeRtosError> {
+        self.get().lock(max_wait)
+    }
+
+    /// Like [`lock`](StaticMutex::lock), but returns the guard of a poisoned lock
+    /// inside the error, see [`MutexImpl::lock_poisoned`].
+    #[cfg(feature = "poison")]
+    pub fn lock_poisoned<D: DurationTicks>(
+        &'static self,
+        max_wait: D,
+    ) -> LockResult<MutexGuard<'static, T, MutexNormal>> {
+        self.get().lock_poisoned(max_wait)
+    }
+
+    /// Get mutable reference to inner value.
+    ///
+    /// This method does not lock the mutex because mutable reference guarantees exclusive access.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.mutex.get_mut().get_mut()
+    }
+}
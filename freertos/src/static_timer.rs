@@ -0,0 +1,102 @@
+use crate::base::*;
+use crate::base_type::v1::*;
+use crate::shim::*;
+use crate::timers::*;
+use crate::units::*;
+use core::ffi::CStr;
+use core::mem::ManuallyDrop;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// Number of words reserved for the kernel's `StaticTimer_t`.
+///
+/// The shim checks at compile time that the structure of the configured port fits,
+/// against its copy `FREERTOS_RS_STATIC_TIMER_WORDS`, which has to be changed with it.
+pub const STATIC_TIMER_WORDS: usize = 16;
+
+#[repr(C)]
+struct StaticTimerBuffer([usize; STATIC_TIMER_WORDS]);
+
+unsafe impl<F: Send> Send for StaticTimer<F> {}
+unsafe impl<F: Send> Sync for StaticTimer<F> {}
+
+/// A software [`Timer`] with its kernel object and callback stored inline, for use in `static` items.
+///
+/// The kernel timer is created with `xTimerCreateStatic` by [`init`](StaticTimer::init),
+/// which needs no heap and can't fail. A static timer is never deleted.
+/// Requires the `static-allocation` feature.
+///
+/// ```ignore
+/// static BLINK: StaticTimer<fn(&Timer)> = StaticTimer::new(c"blink", true, |_| led_toggle());
+///
+/// BLINK.init(Duration::ms(500)).start(Duration::zero())?;
+/// ```
+pub struct StaticTimer<F> {
+    initialized: AtomicBool,
+    name: &'static CStr,
+    auto_reload: bool,
+    buffer: UnsafeCell<StaticTimerBuffer>,
+    timer: UnsafeCell<ManuallyDrop<Timer>>,
+    callback: UnsafeCell<F>,
+}
+
+impl<F> StaticTimer<F>
+where
+    F: FnMut(&Timer) + Send,
+{
+    /// The callback runs in the timer daemon task, one call at a time.
+    pub const fn new(name: &'static CStr, auto_reload: bool, callback: F) -> Self {
+        Self {
+            initialized: AtomicBool::new(false),
+            name,
+            auto_reload,
+            buffer: UnsafeCell::new(StaticTimerBuffer([0; STATIC_TIMER_WORDS])),
+            timer: UnsafeCell::new(ManuallyDrop::new(Timer::empty())),
+            callback: UnsafeCell::new(callback),
+        }
+    }
+
+    /// Create the kernel timer with the given period on the first call and return it.
+    ///
+    /// Later calls return the same timer and ignore `period`. The kernel keeps pointers into
+    /// the inline buffer, so the timer must not move anymore. That is why this takes
+    /// a `'static` reference.
+    pub fn init<D: DurationTicks>(&'static self, period: D) -> &'static Timer {
+        if !self.initialized.load(Ordering::Acquire) {
+            critical_section::with(|_| {
+                if !self.initialized.load(Ordering::Relaxed) {
+                    unsafe {
+                        let handle = freertos_rs_timer_create_static(
+                            self.name.as_ptr() as FreeRtosCharPtr,
+                            period.to_ticks(),
+                            if self.auto_reload { 1 } else { 0 },
+                            self as *const Self as FreeRtosVoidPtr,
+                            Self::timer_callback,
+                            self.buffer.get() as FreeRtosMutVoidPtr,
+                            mem::size_of::<StaticTimerBuffer>(),
+                        );
+                        *self.timer.get() = ManuallyDrop::new(Timer::from_raw_handle(handle));
+                    }
+                    self.initialized.store(true, Ordering::Release);
+                }
+            });
+        }
+        unsafe { &*self.timer.get() }
+    }
+
+    /// Get the timer if it has been created by [`init`](StaticTimer::init).
+    pub fn get(&'static self) -> Option<&'static Timer> {
+        if self.initialized.load(Ordering::Acquire) {
+            Some(unsafe { &*self.timer.get() })
+        } else {
+            None
+        }
+    }
+
+    extern "C" fn timer_callback(handle: FreeRtosTimerHandle) {
+        unsafe {
+            let timer = ManuallyDrop::new(Timer::from_raw_handle(handle));
+            let this = &*(freertos_rs_timer_get_id(handle) as *const Self);
+            (*this.callback.get())(&timer);
+        }
+    }
+}
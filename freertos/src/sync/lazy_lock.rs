@@ -0,0 +1,46 @@
+use crate::base_type::v1::*;
+use crate::sync::OnceLock;
+
+/// A value that is initialized on first access, see [`OnceLock`] for the blocking rules.
+///
+/// ```ignore
+/// static CONFIG: LazyLock<Config> = LazyLock::new(|| Config::load(&FLASH));
+///
+/// let baud = CONFIG.baud_rate;
+/// ```
+pub struct LazyLock<T, F = fn() -> T> {
+    once: OnceLock<T>,
+    init: UnsafeCell<Option<F>>,
+}
+
+unsafe impl<T: Send, F: Send> Send for LazyLock<T, F> {}
+// SAFETY: `init` is only taken by the task that won the right to initialize `once`.
+unsafe impl<T: Send + Sync, F: Send> Sync for LazyLock<T, F> {}
+
+impl<T, F: FnOnce() -> T> LazyLock<T, F> {
+    pub const fn new(f: F) -> Self {
+        Self {
+            once: OnceLock::new(),
+            init: UnsafeCell::new(Some(f)),
+        }
+    }
+
+    /// Initialize the value if needed and return it, the same as dereferencing.
+    pub fn force(this: &Self) -> &T {
+        this.once.get_or_init(|| {
+            let f = unsafe { (*this.init.get()).take() };
+            match f {
+                Some(f) => f(),
+                None => panic!("LazyLock initializer panicked earlier"),
+            }
+        })
+    }
+}
+
+impl<T, F: FnOnce() -> T> Deref for LazyLock<T, F> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        LazyLock::force(self)
+    }
+}
@@ -0,0 +1,7 @@
+//! One-time initialization that blocks waiting tasks instead of spinning.
+
+mod lazy_lock;
+mod once_lock;
+
+pub use lazy_lock::*;
+pub use once_lock::*;
@@ -0,0 +1,202 @@
+use crate::base_type::v1::*;
+use crate::shim::*;
+use crate::task::*;
+use crate::units::*;
+use crate::wait_queue::WaitQueue;
+use core::convert::Infallible;
+use core::ffi::c_void;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicPtr, AtomicU8, Ordering};
+
+const INCOMPLETE: u8 = 0;
+const RUNNING: u8 = 1;
+const COMPLETE: u8 = 2;
+
+/// A cell that is written once, by the first task that initializes it.
+///
+/// Tasks that want the value while another task runs the initializer block until it
+/// is done, without spinning. Initialization also works before
+/// [`start_scheduler`](FreeRtosUtils::start_scheduler), for example to set up drivers
+/// in `main`. If the initializer fails or panics, the next caller runs its own.
+///
+/// Calling back into the same cell from its initializer panics, as does waiting for
+/// another task's initializer from an ISR or while the scheduler is suspended.
+///
+/// ```ignore
+/// static UART: OnceLock<Uart> = OnceLock::new();
+///
+/// let uart = UART.get_or_init(|| Uart::new(UART0, 115_200));
+/// ```
+pub struct OnceLock<T> {
+    state: AtomicU8,
+    /// The task running the initializer, to detect reentrant initialization.
+    initializer: AtomicPtr<c_void>,
+    waiters: WaitQueue,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+unsafe impl<T: Send> Send for OnceLock<T> {}
+unsafe impl<T: Send + Sync> Sync for OnceLock<T> {}
+
+impl<T> OnceLock<T> {
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicU8::new(INCOMPLETE),
+            initializer: AtomicPtr::new(core::ptr::null_mut()),
+            waiters: WaitQueue::new(),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// Get the value if the cell is initialized, without blocking.
+    pub fn get(&self) -> Option<&T> {
+        if self.state.load(Ordering::Acquire) == COMPLETE {
+            Some(unsafe { (*self.value.get()).assume_init_ref() })
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        if *self.state.get_mut() == COMPLETE {
+            Some(unsafe { self.value.get_mut().assume_init_mut() })
+        } else {
+            None
+        }
+    }
+
+    /// Initialize the cell with `value`, or give it back if the cell was already initialized.
+    pub fn set(&self, value: T) -> Result<(), T> {
+        let mut value = Some(value);
+        self.get_or_init(|| value.take().unwrap());
+        match value {
+            None => Ok(()),
+            Some(value) => Err(value),
+        }
+    }
+
+    /// Get the value, running `f` first if the cell is not initialized yet.
+    pub fn get_or_init<F: FnOnce() -> T>(&self, f: F) -> &T {
+        match self.get_or_try_init(|| Ok::<T, Infallible>(f())) {
+            Ok(value) => value,
+            Err(e) => match e {},
+        }
+    }
+
+    /// Get the value, running `f` first if the cell is not initialized yet.
+    /// If `f` fails the cell stays uninitialized and the error is returned.
+    pub fn get_or_try_init<F, E>(&self, f: F) -> Result<&T, E>
+    where
+        F: FnOnce() -> Result<T, E>,
+    {
+        loop {
+            if let Some(value) = self.get() {
+                return Ok(value);
+            }
+            match self.state.compare_exchange(
+                INCOMPLETE,
+                RUNNING,
+                Ordering::Acquire,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return self.initialize(f),
+                Err(_) => self.wait_for_initializer(),
+            }
+        }
+    }
+
+    /// Take the value out of the cell, leaving it uninitialized.
+    pub fn take(&mut self) -> Option<T> {
+        if *self.state.get_mut() == COMPLETE {
+            *self.state.get_mut() = INCOMPLETE;
+            Some(unsafe { self.value.get_mut().assume_init_read() })
+        } else {
+            None
+        }
+    }
+
+    pub fn into_inner(mut self) -> Option<T> {
+        self.take()
+    }
+
+    fn initialize<F, E>(&self, f: F) -> Result<&T, E>
+    where
+        F: FnOnce() -> Result<T, E>,
+    {
+        self.initializer.store(
+            unsafe { freertos_rs_get_current_task() } as *mut _,
+            Ordering::Relaxed,
+        );
+        // Hands the cell to the next caller if `f` fails or panics.
+        let reset = InitReset { once: self };
+        let value = f()?;
+        core::mem::forget(reset);
+
+        unsafe { (*self.value.get()).write(value) };
+        self.finish(COMPLETE);
+        Ok(unsafe { (*self.value.get()).assume_init_ref() })
+    }
+
+    fn finish(&self, state: u8) {
+        self.initializer
+            .store(core::ptr::null_mut(), Ordering::Relaxed);
+        self.state.store(state, Ordering::Release);
+        self.waiters.notify_all();
+    }
+
+    fn wait_for_initializer(&self) {
+        // Before the scheduler runs there is only one thread of execution,
+        // so an initializer that is still running must have called us.
+        let reentrant = FreeRtosUtils::scheduler_state() == FreeRtosSchedulerState::NotStarted
+            || core::ptr::eq(self.initializer.load(Ordering::Relaxed), unsafe {
+                freertos_rs_get_current_task()
+            });
+        if reentrant {
+            panic!("OnceLock initialized reentrantly");
+        }
+        if FreeRtosUtils::scheduler_state() != FreeRtosSchedulerState::Running
+            || unsafe { freertos_rs_is_in_isr() } != 0
+        {
+            panic!("OnceLock is being initialized and the caller can't block");
+        }
+
+        self.waiters.wait_unless(Duration::infinite(), || {
+            self.state.load(Ordering::Acquire) != RUNNING
+        });
+    }
+}
+
+impl<T> Default for OnceLock<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for OnceLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut d = f.debug_tuple("OnceLock");
+        match self.get() {
+            Some(value) => d.field(value),
+            None => d.field(&format_args!("<uninit>")),
+        };
+        d.finish()
+    }
+}
+
+impl<T> Drop for OnceLock<T> {
+    fn drop(&mut self) {
+        if *self.state.get_mut() == COMPLETE {
+            unsafe { self.value.get_mut().assume_init_drop() };
+        }
+    }
+}
+
+struct InitReset<'a, T> {
+    once: &'a OnceLock<T>,
+}
+
+impl<T> Drop for InitReset<'_, T> {
+    fn drop(&mut self) {
+        self.once.finish(INCOMPLETE);
+    }
+}
@@ -15,7 +15,7 @@ pub struct Task {
 }
 
 /// Task's execution priority. Low priority numbers denote low priority tasks.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct TaskPriority(pub u8);
 
 /// Notification to be sent to a task.
@@ -220,17 +220,37 @@ impl Task {
         }
     }
 
-    /// Get the name of the current task.
+    /// Get the name of the task.
+    #[deprecated(note = "use `name`, which doesn't allocate")]
     pub fn get_name(&self) -> Result<String, ()> {
-        unsafe {
-            let name_ptr = freertos_rs_task_get_name(self.task_handle);
-            let name = str_from_c_string(name_ptr);
-            if let Ok(name) = name {
-                return Ok(name.to_string());
-            }
+        Ok(self.name().as_str().to_string())
+    }
 
-            Err(())
-        }
+    /// Get the name of the task without allocating.
+    ///
+    /// The name is cut to [`TASK_NAME_CAPACITY`] bytes, a name that isn't valid UTF-8
+    /// reads as `"?"`.
+    pub fn name(&self) -> TaskName {
+        let name = unsafe { str_from_c_string(freertos_rs_task_get_name(self.task_handle)) };
+        TaskName::new(name.unwrap_or("?"))
+    }
+
+    /// Get the current priority of the task, which includes priority inheritance.
+    #[cfg(feature = "task-priority")]
+    pub fn priority(&self) -> TaskPriority {
+        TaskPriority(unsafe { freertos_rs_task_get_priority(self.task_handle) } as u8)
+    }
+
+    /// Get the priority the task was created or last set with, ignoring priority inheritance.
+    #[cfg(feature = "task-priority")]
+    pub fn base_priority(&self) -> TaskPriority {
+        TaskPriority(unsafe { freertos_rs_task_get_base_priority(self.task_handle) } as u8)
+    }
+
+    /// Change the priority of the task.
+    #[cfg(feature = "task-priority")]
+    pub fn set_priority(&self, priority: TaskPriority) {
+        unsafe { freertos_rs_task_set_priority(self.task_handle, priority.to_freertos()) }
     }
 
     /// Try to find the task of the current execution context.
@@ -486,6 +506,18 @@ impl FreeRtosUtils {
         }
     }
 
+    /// The tick count extended to 64 bits with the kernel's overflow count, so it doesn't wrap.
+    #[inline]
+    pub fn get_tick_count64() -> u64 {
+        unsafe {
+            if is_in_isr() {
+                freertos_rs_get_tick_count64_from_isr()
+            } else {
+                freertos_rs_get_tick_count64()
+            }
+        }
+    }
+
     pub fn get_tick_count_duration() -> Duration {
         Duration::ticks(Self::get_tick_count())
     }
@@ -0,0 +1,135 @@
+//! `embassy-time` driver on the FreeRTOS tick.
+//!
+//! [`embassy_time_driver::now`] is the 64-bit tick count of [`Instant64`] scaled to
+//! [`TICK_HZ`], so `embassy-time` can use any `tick-hz-*` feature. Picking the
+//! kernel's `configTICK_RATE_HZ` avoids the conversion.
+//!
+//! Timers wait in the generic [`Queue`], which works with wakers of any executor and
+//! holds 64 timers unless a `generic-queue-*` feature of `embassy-time` is enabled.
+//! They are woken by a FreeRTOS timer named `embassy-time`, so the timer
+//! daemon task has to be enabled, see `use_timer_task` of `freertos-build`.
+
+use crate::base::*;
+use crate::base_type::v1::*;
+use crate::instant::*;
+use crate::timers::*;
+use crate::units::*;
+use core::cell::RefCell;
+use core::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+use core::task::Waker;
+use embassy_time_driver::{Driver, TICK_HZ};
+use embassy_time_queue_utils::Queue;
+
+struct FreeRtosTimeDriver {
+    queue: critical_section::Mutex<RefCell<Queue>>,
+    /// Created on first use, never freed.
+    alarm: AtomicPtr<Timer>,
+    /// The last alarm couldn't be set. A running timer fires again with its old period
+    /// and the callback retries, a stopped timer is set by the next `schedule_wake`.
+    rearm: AtomicBool,
+}
+
+embassy_time_driver::time_driver_impl!(static DRIVER: FreeRtosTimeDriver = FreeRtosTimeDriver {
+    queue: critical_section::Mutex::new(RefCell::new(Queue::new())),
+    alarm: AtomicPtr::new(core::ptr::null_mut()),
+    rearm: AtomicBool::new(false),
+});
+
+fn tick_rate_hz() -> u64 {
+    FreeRtosTimeUnitsShimmed::get_tick_rate_hz() as u64
+}
+
+/// Kernel ticks to `embassy-time` ticks, rounded down.
+fn to_embassy_ticks(ticks: u64) -> u64 {
+    let rate = tick_rate_hz();
+    if rate == TICK_HZ {
+        return ticks;
+    }
+    (ticks as u128 * TICK_HZ as u128 / rate as u128) as u64
+}
+
+/// `embassy-time` ticks to kernel ticks, rounded up so that alarms never fire early.
+fn to_kernel_ticks(ticks: u64) -> u64 {
+    let rate = tick_rate_hz();
+    if rate == TICK_HZ {
+        return ticks;
+    }
+    (ticks as u128 * rate as u128)
+        .div_ceil(TICK_HZ as u128)
+        .min(u64::MAX as u128) as u64
+}
+
+impl FreeRtosTimeDriver {
+    fn alarm(&self) -> &Timer {
+        let timer = self.alarm.load(Ordering::Acquire);
+        if !timer.is_null() {
+            return unsafe { &*timer };
+        }
+
+        // Auto-reload, so the daemon task keeps the timer armed when the callback
+        // can't set the next alarm because the timer command queue is full.
+        let timer = Timer::new(Duration::ticks(1))
+            .set_name("embassy-time")
+            .set_auto_reload(true)
+            .create(|_| DRIVER.on_alarm())
+            .expect("no memory for the embassy-time timer");
+        let timer = Box::into_raw(Box::new(timer));
+        match self.alarm.compare_exchange(
+            core::ptr::null_mut(),
+            timer,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            Ok(_) => unsafe { &*timer },
+            Err(existing) => {
+                drop(unsafe { Box::from_raw(timer) });
+                unsafe { &*existing }
+            }
+        }
+    }
+
+    /// Must be called in the critical section that holds the queue, so that the
+    /// last alarm set is for the earliest timer.
+    fn set_alarm(&self, alarm: &Timer, at: u64) {
+        let res = if at == u64::MAX {
+            alarm.stop(Duration::zero())
+        } else {
+            let ticks = to_kernel_ticks(at).saturating_sub(Instant64::now().ticks());
+            // The callback sets the next alarm when a long wait is cut short.
+            let max = (FreeRtosTimeUnitsShimmed::get_max_wait() / 2) as u64;
+            let ticks = ticks.clamp(1, max) as FreeRtosTickType;
+            alarm.change_period(Duration::zero(), Duration::ticks(ticks))
+        };
+        // Can't block here when the timer command queue is full, so try again later.
+        self.rearm.store(res.is_err(), Ordering::Relaxed);
+    }
+
+    /// Runs in the timer daemon task, which already reloaded the timer. If the next
+    /// alarm can't be set, this runs again after the old period.
+    fn on_alarm(&self) {
+        let alarm = self.alarm();
+        critical_section::with(|cs| {
+            let mut queue = self.queue.borrow_ref_mut(cs);
+            let next = queue.next_expiration(self.now());
+            self.set_alarm(alarm, next);
+        });
+    }
+}
+
+impl Driver for FreeRtosTimeDriver {
+    fn now(&self) -> u64 {
+        to_embassy_ticks(Instant64::now().ticks())
+    }
+
+    fn schedule_wake(&self, at: u64, waker: &Waker) {
+        // The timer is created outside of the critical section, as that allocates.
+        let alarm = self.alarm();
+        critical_section::with(|cs| {
+            let mut queue = self.queue.borrow_ref_mut(cs);
+            if queue.schedule_wake(at, waker) || self.rearm.load(Ordering::Relaxed) {
+                let next = queue.next_expiration(self.now());
+                self.set_alarm(alarm, next);
+            }
+        });
+    }
+}
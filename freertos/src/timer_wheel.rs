@@ -0,0 +1,483 @@
+use crate::base::*;
+use crate::base_type::v1::*;
+use crate::mutex::*;
+#[cfg(feature = "poison")]
+use crate::poison::*;
+use crate::queue::*;
+use crate::task::*;
+use crate::units::*;
+
+const LEVELS: usize = 4;
+const SLOT_BITS: u32 = 6;
+const SLOTS: usize = 1 << SLOT_BITS;
+/// Delays up to this many ticks are placed exactly, longer ones are placed in the
+/// last level and moved down when they come in range.
+const MAX_SPAN: u64 = 1 << (SLOT_BITS * LEVELS as u32);
+const NIL: u32 = u32::MAX;
+const UNLINKED: u16 = u16::MAX;
+
+/// Identifies a timeout scheduled on a [`TimerWheel`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TimerId {
+    index: u32,
+    generation: u32,
+}
+
+/// What a [`TimerWheel`] does with the payload of an expired timeout.
+pub enum WheelDelivery<T: Send> {
+    /// Call the function from the wheel's task.
+    Callback(fn(T)),
+    /// Send the payload to the queue without waiting, it is dropped if the queue is full.
+    Queue(Arc<Queue<T>>),
+}
+
+/// How timely the timeouts of a [`TimerWheel`] fired.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct TimerWheelStats {
+    /// Number of expired timeouts.
+    pub fired: u64,
+    /// Expired timeouts whose payload couldn't be sent because the queue was full.
+    pub dropped: u64,
+    /// Sum of the ticks between the deadlines and the moments the timeouts fired.
+    pub total_lateness: u64,
+    /// The latest a timeout fired, in ticks.
+    pub max_lateness: FreeRtosTickType,
+}
+
+impl TimerWheelStats {
+    /// Average lateness in ticks.
+    pub fn mean_lateness(&self) -> FreeRtosTickType {
+        self.total_lateness.checked_div(self.fired).unwrap_or(0) as FreeRtosTickType
+    }
+}
+
+struct Entry<T> {
+    payload: Option<T>,
+    deadline: u64,
+    generation: u32,
+    prev: u32,
+    next: u32,
+    /// `level * SLOTS + slot`, or [`UNLINKED`].
+    slot: u16,
+}
+
+/// The next slot to process and the tick it is due.
+#[derive(Copy, Clone)]
+struct Expiration {
+    level: usize,
+    slot: usize,
+    deadline: u64,
+}
+
+/// A hierarchical timing wheel: level `n` has 64 slots of `64^n` ticks each.
+struct Wheel<T> {
+    entries: Vec<Entry<T>>,
+    capacity: usize,
+    free: u32,
+    len: usize,
+    heads: [[u32; SLOTS]; LEVELS],
+    occupied: [u64; LEVELS],
+    /// The wheel has processed every slot up to this tick.
+    elapsed: u64,
+    /// When the service task wakes up next, `u64::MAX` while it has nothing to do.
+    next_wake: u64,
+    stats: TimerWheelStats,
+}
+
+impl<T> Wheel<T> {
+    /// `tick` is the current 64-bit tick count.
+    fn new(capacity: usize, tick: u64) -> Self {
+        Self {
+            entries: Vec::with_capacity(capacity),
+            capacity,
+            free: NIL,
+            len: 0,
+            heads: [[NIL; SLOTS]; LEVELS],
+            occupied: [0; LEVELS],
+            elapsed: tick,
+            next_wake: u64::MAX,
+            stats: TimerWheelStats::default(),
+        }
+    }
+
+    fn insert(&mut self, deadline: u64, payload: T) -> Result<TimerId, T> {
+        let index = if self.free != NIL {
+            let index = self.free;
+            self.free = self.entries[index as usize].next;
+            index
+        } else if self.entries.len() < self.capacity {
+            self.entries.push(Entry {
+                payload: None,
+                deadline: 0,
+                generation: 0,
+                prev: NIL,
+                next: NIL,
+                slot: UNLINKED,
+            });
+            (self.entries.len() - 1) as u32
+        } else {
+            return Err(payload);
+        };
+
+        let entry = &mut self.entries[index as usize];
+        entry.payload = Some(payload);
+        entry.deadline = deadline;
+        let generation = entry.generation;
+        self.len += 1;
+        self.link(index);
+        Ok(TimerId { index, generation })
+    }
+
+    fn remove(&mut self, id: TimerId) -> Option<T> {
+        let entry = self.entries.get(id.index as usize)?;
+        if entry.generation != id.generation || entry.payload.is_none() {
+            return None;
+        }
+        self.unlink(id.index);
+        Some(self.release(id.index))
+    }
+
+    /// Take the payload and put the entry on the free list, invalidating its [`TimerId`].
+    fn release(&mut self, index: u32) -> T {
+        let entry = &mut self.entries[index as usize];
+        entry.generation = entry.generation.wrapping_add(1);
+        entry.next = self.free;
+        self.free = index;
+        self.len -= 1;
+        entry.payload.take().unwrap()
+    }
+
+    fn link(&mut self, index: u32) {
+        // A deadline in the past fires with the next processed slot.
+        let deadline = self.entries[index as usize].deadline.max(self.elapsed + 1);
+        let placed = deadline.min(self.elapsed + MAX_SPAN - 1);
+        let significant = 63 - ((self.elapsed ^ placed) | (SLOTS as u64 - 1)).leading_zeros();
+        // Crossing a multiple of `MAX_SPAN` gives one level too many, the last level wraps
+        // around and the entry is moved down when its slot comes up.
+        let level = ((significant / SLOT_BITS) as usize).min(LEVELS - 1);
+        let slot = ((placed >> (level as u32 * SLOT_BITS)) as usize) & (SLOTS - 1);
+
+        let head = self.heads[level][slot];
+        let entry = &mut self.entries[index as usize];
+        entry.slot = (level * SLOTS + slot) as u16;
+        entry.prev = NIL;
+        entry.next = head;
+        if head != NIL {
+            self.entries[head as usize].prev = index;
+        }
+        self.heads[level][slot] = index;
+        self.occupied[level] |= 1 << slot;
+    }
+
+    fn unlink(&mut self, index: u32) {
+        let entry = &mut self.entries[index as usize];
+        let (prev, next) = (entry.prev, entry.next);
+        let (level, slot) = (entry.slot as usize / SLOTS, entry.slot as usize % SLOTS);
+        entry.slot = UNLINKED;
+
+        if prev == NIL {
+            self.heads[level][slot] = next;
+            if next == NIL {
+                self.occupied[level] &= !(1 << slot);
+            }
+        } else {
+            self.entries[prev as usize].next = next;
+        }
+        if next != NIL {
+            self.entries[next as usize].prev = prev;
+        }
+    }
+
+    fn next_expiration(&self) -> Option<Expiration> {
+        // Entries in lower levels always expire before those in higher levels.
+        let level = self.occupied.iter().position(|o| *o != 0)?;
+        let shift = level as u32 * SLOT_BITS;
+        let level_range = 1u64 << (shift + SLOT_BITS);
+
+        let now_slot = ((self.elapsed >> shift) as usize) & (SLOTS - 1);
+        let distance = self.occupied[level]
+            .rotate_right(now_slot as u32)
+            .trailing_zeros() as usize;
+        let slot = (now_slot + distance) % SLOTS;
+
+        let mut deadline = (self.elapsed & !(level_range - 1)) + ((slot as u64) << shift);
+        if deadline <= self.elapsed {
+            // Only entries beyond `MAX_SPAN` end up behind the current slot.
+            deadline += level_range;
+        }
+        Some(Expiration {
+            level,
+            slot,
+            deadline,
+        })
+    }
+
+    /// Process all slots due at `now`, pushing the payloads of expired entries to `expired`.
+    fn advance(&mut self, now: u64, expired: &mut Vec<T>) {
+        while let Some(expiration) = self.next_expiration() {
+            if expiration.deadline > now {
+                break;
+            }
+            self.elapsed = expiration.deadline;
+
+            let mut index =
+                core::mem::replace(&mut self.heads[expiration.level][expiration.slot], NIL);
+            self.occupied[expiration.level] &= !(1 << expiration.slot);
+            while index != NIL {
+                let next = self.entries[index as usize].next;
+                let deadline = self.entries[index as usize].deadline;
+                if deadline <= self.elapsed {
+                    let lateness = now - deadline;
+                    self.stats.fired += 1;
+                    self.stats.total_lateness += lateness;
+                    self.stats.max_lateness = self
+                        .stats
+                        .max_lateness
+                        .max(lateness.min(FreeRtosTickType::MAX as u64) as FreeRtosTickType);
+                    self.entries[index as usize].slot = UNLINKED;
+                    expired.push(self.release(index));
+                } else {
+                    self.link(index);
+                }
+                index = next;
+            }
+        }
+        self.elapsed = self.elapsed.max(now);
+    }
+}
+
+struct Shared<T: Send> {
+    wheel: Mutex<Wheel<T>>,
+    delivery: WheelDelivery<T>,
+}
+
+impl<T: Send> Shared<T> {
+    fn lock(&self) -> Result<MutexGuard<'_, Wheel<T>, MutexNormal>, FreeRtosError> {
+        // The wheel is never left inconsistent, callbacks run without the lock.
+        #[cfg(feature = "poison")]
+        return ignore_poison(self.wheel.lock_poisoned(Duration::infinite()));
+        #[cfg(not(feature = "poison"))]
+        self.wheel.lock(Duration::infinite())
+    }
+}
+
+/// Helper builder for a [`TimerWheel`]. Instantiate with [`TimerWheel::builder()`].
+pub struct TimerWheelBuilder<T> {
+    capacity: usize,
+    name: String,
+    stack_size: u16,
+    priority: TaskPriority,
+    _payload: PhantomData<T>,
+}
+
+impl<T: Send + 'static> TimerWheelBuilder<T> {
+    /// Set the name of the service task.
+    pub fn set_name(&mut self, name: &str) -> &mut Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Set the stack size of the service task, in words.
+    pub fn set_stack_size(&mut self, stack_size: u16) -> &mut Self {
+        self.stack_size = stack_size;
+        self
+    }
+
+    /// Set the priority of the service task, callbacks run at this priority.
+    pub fn set_priority(&mut self, priority: TaskPriority) -> &mut Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Allocate the timeouts and start the service task.
+    pub fn start(&self, delivery: WheelDelivery<T>) -> Result<TimerWheel<T>, FreeRtosError> {
+        let shared = Arc::new(Shared {
+            wheel: Mutex::new(Wheel::new(self.capacity, FreeRtosUtils::get_tick_count64()))?,
+            delivery,
+        });
+
+        let service = shared.clone();
+        let task = Task::new()
+            .name(&self.name)
+            .stack_size(self.stack_size)
+            .priority(self.priority)
+            .start(move |_| run_service(&service))?;
+
+        Ok(TimerWheel { shared, task })
+    }
+}
+
+fn run_service<T: Send>(shared: &Shared<T>) -> ! {
+    let mut expired = Vec::new();
+    loop {
+        let wait = match shared.lock() {
+            Ok(mut wheel) => {
+                let now = FreeRtosUtils::get_tick_count64();
+                wheel.advance(now, &mut expired);
+                let next = wheel.next_expiration().map(|e| e.deadline);
+                wheel.next_wake = next.unwrap_or(u64::MAX);
+
+                let infinite = Duration::infinite().to_ticks();
+                // A wait of `infinite` never times out, a far deadline wakes up earlier.
+                let ticks = next.map_or(infinite, |d| {
+                    (d.saturating_sub(now)).min(infinite as u64 - 1) as FreeRtosTickType
+                });
+                if let WheelDelivery::Queue(queue) = &shared.delivery {
+                    for payload in expired.drain(..) {
+                        if queue.send(payload, Duration::zero()).is_err() {
+                            wheel.stats.dropped += 1;
+                        }
+                    }
+                }
+                Duration::ticks(ticks)
+            }
+            Err(_) => Duration::eps(),
+        };
+
+        if let WheelDelivery::Callback(callback) = shared.delivery {
+            for payload in expired.drain(..) {
+                callback(payload);
+            }
+        }
+
+        CurrentTask::take_notification(true, wait);
+    }
+}
+
+/// Many lightweight timeouts served by a single task.
+///
+/// Unlike a [`Timer`](crate::Timer), a timeout isn't a kernel object: scheduling and
+/// cancelling only update a hierarchical timing wheel in constant time, and the service
+/// task sleeps until the next slot is due. The timeouts are allocated up front, so
+/// scheduling never allocates. Every timeout carries a payload, which is passed to a
+/// callback or sent to a queue when it expires.
+///
+/// ```ignore
+/// fn retransmit(seq: u32) { /* ... */ }
+///
+/// let wheel = TimerWheel::builder(1024).set_priority(TaskPriority(3)).start(WheelDelivery::Callback(retransmit))?;
+/// let id = wheel.schedule(Duration::ms(200), seq)?;
+/// // acknowledged in time
+/// wheel.cancel(id);
+/// ```
+pub struct TimerWheel<T: Send> {
+    shared: Arc<Shared<T>>,
+    task: Task,
+}
+
+impl<T: Send> Clone for TimerWheel<T> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+            task: self.task.clone(),
+        }
+    }
+}
+
+impl<T: Send + 'static> TimerWheel<T> {
+    /// Create a builder for a wheel that holds up to `capacity` timeouts.
+    pub fn builder(capacity: usize) -> TimerWheelBuilder<T> {
+        TimerWheelBuilder {
+            capacity,
+            name: "timer_wheel".into(),
+            stack_size: 256,
+            priority: TaskPriority(2),
+            _payload: PhantomData,
+        }
+    }
+
+    /// Schedule `payload` to be delivered after `delay`.
+    ///
+    /// Fails with [`FreeRtosError::OutOfMemory`] if all timeouts are in use,
+    /// the payload is returned in the error.
+    pub fn schedule<D: DurationTicks>(
+        &self,
+        delay: D,
+        payload: T,
+    ) -> Result<TimerId, SendError<T>> {
+        let mut wheel = match self.shared.lock() {
+            Ok(wheel) => wheel,
+            Err(e) => return Err(SendError::new(e, payload)),
+        };
+        let deadline = FreeRtosUtils::get_tick_count64() + delay.to_ticks() as u64;
+        let id = wheel
+            .insert(deadline, payload)
+            .map_err(|payload| SendError::new(FreeRtosError::OutOfMemory, payload))?;
+
+        if deadline < wheel.next_wake {
+            wheel.next_wake = deadline;
+            drop(wheel);
+            self.task.notify(TaskNotification::Increment);
+        }
+        Ok(id)
+    }
+
+    /// Cancel a timeout, returning its payload if it didn't expire yet.
+    pub fn cancel(&self, id: TimerId) -> Option<T> {
+        self.shared.lock().ok()?.remove(id)
+    }
+
+    /// Number of scheduled timeouts.
+    pub fn len(&self) -> usize {
+        self.shared.lock().map_or(0, |wheel| wheel.len)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn stats(&self) -> TimerWheelStats {
+        self.shared
+            .lock()
+            .map(|wheel| wheel.stats)
+            .unwrap_or_default()
+    }
+
+    pub fn reset_stats(&self) {
+        if let Ok(mut wheel) = self.shared.lock() {
+            wheel.stats = TimerWheelStats::default();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wheel_at(elapsed: u64) -> Wheel<u32> {
+        let mut wheel = Wheel::new(4, 0);
+        wheel.elapsed = elapsed;
+        wheel
+    }
+
+    #[test]
+    fn timeout_across_max_span_boundary() {
+        let start = MAX_SPAN - 5;
+        let mut wheel = wheel_at(start);
+        wheel.insert(start + 10, 1).unwrap();
+
+        let mut expired = Vec::new();
+        wheel.advance(start + 9, &mut expired);
+        assert!(expired.is_empty());
+        wheel.advance(start + 10, &mut expired);
+        assert_eq!(expired, [1]);
+        assert_eq!(wheel.len, 0);
+    }
+
+    #[test]
+    fn long_timeouts_across_max_span_boundary_fire_in_order() {
+        let start = 3 * MAX_SPAN - 5;
+        let mut wheel = wheel_at(start);
+        wheel.insert(start + MAX_SPAN + 100, 3).unwrap();
+        wheel.insert(start + MAX_SPAN - 6, 2).unwrap();
+        wheel.insert(start + 1, 1).unwrap();
+
+        let mut expired = Vec::new();
+        for deadline in [start + 1, start + MAX_SPAN - 6, start + MAX_SPAN + 100] {
+            wheel.advance(deadline - 1, &mut expired);
+            assert_eq!(expired.len(), wheel.entries.len() - wheel.len);
+            wheel.advance(deadline, &mut expired);
+        }
+        assert_eq!(expired, [1, 2, 3]);
+    }
+}
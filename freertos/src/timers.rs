@@ -3,6 +3,7 @@ use crate::base::*;
 use crate::base_type::v1::*;
 use crate::shim::*;
 use crate::units::*;
+use crate::utils::*;
 
 unsafe impl Send for Timer {}
 unsafe impl Sync for Timer {}
@@ -14,13 +15,25 @@ unsafe impl Sync for Timer {}
 /// for that queue to get unblocked.
 pub struct Timer {
     handle: FreeRtosTimerHandle,
+    delete_timeout: FreeRtosTickType,
 }
 
+/// Data of a timer created by this crate, its address is the timer ID.
+struct TimerState {
+    /// Nul-terminated, the kernel only keeps a pointer to the name.
+    name: Box<[u8]>,
+    callback: Box<dyn FnMut(&Timer) + Send>,
+}
+
+/// Default time to wait for the timer command queue when a [`Timer`] is dropped.
+pub const TIMER_DEFAULT_DELETE_TIMEOUT_MS: u32 = 1000;
+
 /// Helper builder for a new software timer.
 pub struct TimerBuilder<D: DurationTicks> {
     name: String,
     period: D,
     auto_reload: bool,
+    delete_timeout: Option<D>,
 }
 
 impl<D: DurationTicks> TimerBuilder<D> {
@@ -42,20 +55,32 @@ impl<D: DurationTicks> TimerBuilder<D> {
         self
     }
 
+    /// Set how long dropping the timer waits for the timer command queue,
+    /// [`TIMER_DEFAULT_DELETE_TIMEOUT_MS`] by default.
+    pub fn set_delete_timeout(&mut self, delete_timeout: D) -> &mut Self {
+        self.delete_timeout = Some(delete_timeout);
+        self
+    }
+
     /// Try to create the new timer.
     ///
-    /// Note that the newly created timer must be started.
+    /// Note that the newly created timer must be started. The callback runs in the timer
+    /// daemon task, which runs callbacks one at a time, so it can keep state between calls.
     pub fn create<F>(&self, callback: F) -> Result<Timer, FreeRtosError>
     where
-        F: Fn(&Timer) -> (),
+        F: FnMut(&Timer),
         F: Send + 'static,
     {
-        Timer::spawn(
+        let mut timer = Timer::spawn(
             self.name.as_str(),
             self.period.to_ticks(),
             self.auto_reload,
             callback,
-        )
+        )?;
+        if let Some(delete_timeout) = self.delete_timeout {
+            timer.set_delete_timeout(delete_timeout);
+        }
+        Ok(timer)
     }
 }
 
@@ -66,6 +91,7 @@ impl Timer {
             name: "timer".into(),
             period: period,
             auto_reload: true,
+            delete_timeout: None,
         }
     }
 
@@ -76,63 +102,68 @@ impl Timer {
     /// `handle` must be a valid FreeRTOS timer handle.
     #[inline]
     pub unsafe fn from_raw_handle(handle: FreeRtosTimerHandle) -> Self {
-        Self { handle }
+        Self {
+            handle,
+            delete_timeout: Duration::ms(TIMER_DEFAULT_DELETE_TIMEOUT_MS).to_ticks(),
+        }
+    }
+    /// A timer without a kernel object, to be filled in by [`StaticTimer`].
+    #[cfg(feature = "static-allocation")]
+    pub(crate) const fn empty() -> Self {
+        Self {
+            handle: core::ptr::null(),
+            delete_timeout: 0,
+        }
     }
+
     #[inline]
     pub fn raw_handle(&self) -> FreeRtosTimerHandle {
         self.handle
     }
 
-    unsafe fn spawn_inner<'a>(
+    unsafe fn spawn_inner(
         name: &str,
         period_ticks: FreeRtosTickType,
         auto_reload: bool,
-        callback: Box<dyn Fn(&Timer) + Send + 'a>,
+        callback: Box<dyn FnMut(&Timer) + Send>,
     ) -> Result<Timer, FreeRtosError> {
-        let f = Box::new(callback);
-        let param_ptr = &*f as *const _ as *mut _;
-
-        let (success, timer_handle) = {
-            let name = name.as_bytes();
-            let name_len = name.len();
-
-            let ret = unsafe {
-                freertos_rs_timer_create(
-                    name.as_ptr(),
-                    name_len as u8,
-                    period_ticks,
-                    if auto_reload { 1 } else { 0 },
-                    param_ptr,
-                    timer_callback,
-                )
-            };
-
-            ((ret as usize) != 0, ret)
+        let mut c_name = Vec::with_capacity(name.len() + 1);
+        c_name.extend(name.bytes().take_while(|b| *b != 0));
+        c_name.push(0);
+
+        let state = Box::new(TimerState {
+            name: c_name.into_boxed_slice(),
+            callback,
+        });
+        let param_ptr = &*state as *const TimerState as *mut _;
+
+        let timer_handle = unsafe {
+            freertos_rs_timer_create(
+                state.name.as_ptr(),
+                period_ticks,
+                if auto_reload { 1 } else { 0 },
+                param_ptr,
+                timer_callback,
+            )
         };
 
-        if success {
-            mem::forget(f);
-        } else {
+        if timer_handle.is_null() {
             return Err(FreeRtosError::OutOfMemory);
         }
+        mem::forget(state);
 
         extern "C" fn timer_callback(handle: FreeRtosTimerHandle) -> () {
             unsafe {
-                {
-                    let timer = Timer { handle };
-                    if let Ok(callback_ptr) = timer.get_id() {
-                        let b = Box::from_raw(callback_ptr as *mut Box<dyn Fn(&Timer)>);
-                        b(&timer);
-                        let _ = Box::into_raw(b);
-                    }
-                    mem::forget(timer);
+                let timer = Timer::from_raw_handle(handle);
+                if let Ok(state_ptr) = timer.get_id() {
+                    let state = &mut *(state_ptr as *mut TimerState);
+                    (state.callback)(&timer);
                 }
+                mem::forget(timer);
             }
         }
 
-        Ok(Timer {
-            handle: timer_handle as *const _,
-        })
+        Ok(unsafe { Timer::from_raw_handle(timer_handle) })
     }
 
     fn spawn<F>(
@@ -142,7 +173,7 @@ impl Timer {
         callback: F,
     ) -> Result<Timer, FreeRtosError>
     where
-        F: Fn(&Timer) -> (),
+        F: FnMut(&Timer),
         F: Send + 'static,
     {
         unsafe { Timer::spawn_inner(name, period_tick, auto_reload, Box::new(callback)) }
@@ -170,6 +201,30 @@ impl Timer {
         }
     }
 
+    /// Start the timer again, so it expires one period from now.
+    ///
+    /// Starts the timer if it is not running.
+    pub fn reset<D: DurationTicks>(&self, block_time: D) -> Result<(), FreeRtosError> {
+        unsafe {
+            if freertos_rs_timer_reset(self.handle, block_time.to_ticks()) == 0 {
+                Ok(())
+            } else {
+                Err(FreeRtosError::Timeout)
+            }
+        }
+    }
+
+    /// Reset the timer from an interrupt.
+    pub fn reset_from_isr(&self, context: &mut InterruptContext) -> Result<(), FreeRtosError> {
+        unsafe {
+            if freertos_rs_timer_reset_from_isr(self.handle, context.get_task_field_mut()) == 0 {
+                Ok(())
+            } else {
+                Err(FreeRtosError::QueueSendTimeout)
+            }
+        }
+    }
+
     /// Stop the timer.
     pub fn stop<D: DurationTicks>(&self, block_time: D) -> Result<(), FreeRtosError> {
         unsafe {
@@ -181,6 +236,17 @@ impl Timer {
         }
     }
 
+    /// Stop the timer from an interrupt.
+    pub fn stop_from_isr(&self, context: &mut InterruptContext) -> Result<(), FreeRtosError> {
+        unsafe {
+            if freertos_rs_timer_stop_from_isr(self.handle, context.get_task_field_mut()) == 0 {
+                Ok(())
+            } else {
+                Err(FreeRtosError::QueueSendTimeout)
+            }
+        }
+    }
+
     /// Change the period of the timer.
     pub fn change_period<D: DurationTicks>(
         &self,
@@ -201,6 +267,62 @@ impl Timer {
         }
     }
 
+    /// Change the period of the timer from an interrupt.
+    pub fn change_period_from_isr<D: DurationTicks>(
+        &self,
+        context: &mut InterruptContext,
+        new_period: D,
+    ) -> Result<(), FreeRtosError> {
+        unsafe {
+            if freertos_rs_timer_change_period_from_isr(
+                self.handle,
+                new_period.to_ticks(),
+                context.get_task_field_mut(),
+            ) == 0
+            {
+                Ok(())
+            } else {
+                Err(FreeRtosError::QueueSendTimeout)
+            }
+        }
+    }
+
+    /// Is the timer running?
+    ///
+    /// A one-shot timer is no longer active once its callback ran.
+    pub fn is_active(&self) -> bool {
+        unsafe { freertos_rs_timer_is_active(self.handle) != 0 }
+    }
+
+    pub fn period(&self) -> Duration {
+        Duration::ticks(unsafe { freertos_rs_timer_get_period(self.handle) })
+    }
+
+    /// The tick count at which the timer expires next. Meaningless if the timer isn't active.
+    pub fn expiry_time(&self) -> FreeRtosTickType {
+        unsafe { freertos_rs_timer_get_expiry_time(self.handle) }
+    }
+
+    /// Switch between auto-reload and one-shot mode.
+    pub fn set_reload_mode(&self, auto_reload: bool) {
+        unsafe { freertos_rs_timer_set_reload_mode(self.handle, auto_reload as u8) }
+    }
+
+    /// `true` if the timer reloads itself after expiring.
+    pub fn get_reload_mode(&self) -> bool {
+        unsafe { freertos_rs_timer_get_reload_mode(self.handle) != 0 }
+    }
+
+    pub fn get_name(&self) -> Result<String, FreeRtosError> {
+        let name = unsafe { str_from_c_string(freertos_rs_timer_get_name(self.handle)) }?;
+        Ok(name.to_string())
+    }
+
+    /// Set how long dropping the timer waits for the timer command queue.
+    pub fn set_delete_timeout<D: DurationTicks>(&mut self, delete_timeout: D) {
+        self.delete_timeout = delete_timeout.to_ticks();
+    }
+
     /// Detach this timer from Rust's memory management. The timer will still be active and
     /// will consume the memory.
     ///
@@ -217,16 +339,28 @@ impl Timer {
 }
 
 impl Drop for Timer {
-    #[allow(unused_must_use)]
     fn drop(&mut self) {
         unsafe {
-            if let Ok(callback_ptr) = self.get_id() {
-                // free the memory
-                Box::from_raw(callback_ptr as *mut Box<dyn Fn(Timer)>);
+            let state_ptr = freertos_rs_timer_get_id(self.handle) as FreeRtosMutVoidPtr;
+
+            if freertos_rs_timer_delete(self.handle, self.delete_timeout) != 0 {
+                // The timer is still alive and may fire, so its callback is leaked.
+                return;
             }
 
-            // todo: configurable timeout?
-            freertos_rs_timer_delete(self.handle, Duration::ms(1000).to_ticks());
+            // The daemon task may run the callback until it processes the delete
+            // command, so the callback is freed by a call queued behind it. If that
+            // call can't be queued either, the callback is leaked.
+            let _ = freertos_rs_timer_pend_function_call(
+                free_timer_state,
+                state_ptr,
+                0,
+                self.delete_timeout,
+            );
+        }
+
+        extern "C" fn free_timer_state(state_ptr: FreeRtosMutVoidPtr, _: u32) {
+            unsafe { drop(Box::from_raw(state_ptr as *mut TimerState)) };
         }
     }
 }
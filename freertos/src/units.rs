@@ -1,22 +1,54 @@
 use crate::base::FreeRtosTickType;
 use crate::base_type::v1::*;
 use crate::shim::*;
+use core::hash::{Hash, Hasher};
+use core::ops::{Add, AddAssign, Mul, Sub, SubAssign};
+use core::sync::atomic::{self, AtomicU32};
 
 pub trait FreeRtosTimeUnits {
     fn get_tick_period_ms() -> u32;
     fn get_max_wait() -> u32;
+    /// Ticks per second.
+    ///
+    /// The default is derived from [`get_tick_period_ms`](FreeRtosTimeUnits::get_tick_period_ms),
+    /// which is only exact for tick rates that divide 1000.
+    fn get_tick_rate_hz() -> u32 {
+        1000 / Self::get_tick_period_ms().max(1)
+    }
 }
 
 #[derive(Copy, Clone, Default)]
 pub struct FreeRtosTimeUnitsShimmed;
+
+static TICK_RATE_HZ: AtomicU32 = AtomicU32::new(0);
+static MAX_WAIT: AtomicU32 = AtomicU32::new(0);
+
+/// Read a kernel constant once, the value 0 marks it as not read yet.
+#[inline]
+fn cached(cache: &AtomicU32, read: impl FnOnce() -> u32) -> u32 {
+    let value = cache.load(atomic::Ordering::Relaxed);
+    if value != 0 {
+        return value;
+    }
+    let value = read();
+    cache.store(value, atomic::Ordering::Relaxed);
+    value
+}
+
 impl FreeRtosTimeUnits for FreeRtosTimeUnitsShimmed {
     #[inline]
     fn get_tick_period_ms() -> u32 {
-        unsafe { freertos_rs_get_portTICK_PERIOD_MS() }
+        1000 / Self::get_tick_rate_hz()
     }
     #[inline]
     fn get_max_wait() -> u32 {
-        unsafe { freertos_rs_max_wait() }
+        cached(&MAX_WAIT, || unsafe { freertos_rs_max_wait() })
+    }
+    #[inline]
+    fn get_tick_rate_hz() -> u32 {
+        cached(&TICK_RATE_HZ, || unsafe {
+            freertos_rs_get_configTICK_RATE_HZ()
+        })
     }
 }
 
@@ -28,7 +60,12 @@ pub trait DurationTicks: Copy + Clone {
 pub type Duration = DurationImpl<FreeRtosTimeUnitsShimmed>;
 
 /// Time unit used by FreeRTOS, passed to the scheduler as ticks.
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+///
+/// The constructors without a rounding suffix round up to whole ticks, so that a wait
+/// never ends early and a short wait doesn't become a poll. Conversions and arithmetic
+/// saturate, and since the largest tick count is [`infinite`](DurationImpl::infinite),
+/// an overflowing duration waits forever.
+#[derive(Copy, Clone)]
 pub struct DurationImpl<T> {
     ticks: u32,
     _time_units: PhantomData<T>,
@@ -38,9 +75,35 @@ impl<T> DurationImpl<T>
 where
     T: FreeRtosTimeUnits + Copy,
 {
-    /// Milliseconds constructor
+    /// Milliseconds constructor, rounded up to whole ticks
     pub fn ms(milliseconds: u32) -> Self {
-        Self::ticks(milliseconds / T::get_tick_period_ms())
+        Self::ms_round_up(milliseconds)
+    }
+
+    pub fn ms_round_up(milliseconds: u32) -> Self {
+        Self::from_fraction(milliseconds as u64, 1_000, true)
+    }
+
+    pub fn ms_round_down(milliseconds: u32) -> Self {
+        Self::from_fraction(milliseconds as u64, 1_000, false)
+    }
+
+    /// Microseconds constructor, rounded up to whole ticks
+    pub fn us(microseconds: u32) -> Self {
+        Self::us_round_up(microseconds)
+    }
+
+    pub fn us_round_up(microseconds: u32) -> Self {
+        Self::from_fraction(microseconds as u64, 1_000_000, true)
+    }
+
+    pub fn us_round_down(microseconds: u32) -> Self {
+        Self::from_fraction(microseconds as u64, 1_000_000, false)
+    }
+
+    /// Seconds constructor
+    pub fn secs(seconds: u32) -> Self {
+        Self::from_fraction(seconds as u64, 1, false)
     }
 
     pub fn ticks(ticks: u32) -> Self {
@@ -65,8 +128,60 @@ where
         Self::ticks(1)
     }
 
+    /// Milliseconds, rounded down
     pub fn to_ms(&self) -> u32 {
-        self.ticks * T::get_tick_period_ms()
+        self.to_fraction(1_000).min(u32::MAX as u64) as u32
+    }
+
+    /// Microseconds, rounded down
+    pub fn to_us(&self) -> u64 {
+        self.to_fraction(1_000_000)
+    }
+
+    /// `value / per_second` seconds
+    fn from_fraction(value: u64, per_second: u64, round_up: bool) -> Self {
+        let scaled = value * T::get_tick_rate_hz() as u64;
+        let ticks = if round_up {
+            scaled.div_ceil(per_second)
+        } else {
+            scaled / per_second
+        };
+        Self::ticks(ticks.min(u32::MAX as u64) as u32)
+    }
+
+    fn to_fraction(self, per_second: u64) -> u64 {
+        self.ticks as u64 * per_second / T::get_tick_rate_hz() as u64
+    }
+
+    /// Convert a `fugit` duration, rounded up to whole ticks.
+    #[cfg(feature = "fugit")]
+    pub fn from_fugit<const NOM: u32, const DENOM: u32>(
+        duration: fugit::Duration<u32, NOM, DENOM>,
+    ) -> Self {
+        Self::from_fugit_ticks(duration.ticks() as u64, NOM, DENOM)
+    }
+
+    /// Convert to a `fugit` duration, rounded down.
+    #[cfg(feature = "fugit")]
+    pub fn to_fugit<const NOM: u32, const DENOM: u32>(&self) -> fugit::Duration<u32, NOM, DENOM> {
+        fugit::Duration::<u32, NOM, DENOM>::from_ticks(
+            self.to_fugit_ticks(NOM, DENOM).min(u32::MAX as u64) as u32,
+        )
+    }
+
+    /// A `fugit` duration of `ticks * nom / denom` seconds, rounded up.
+    #[cfg(feature = "fugit")]
+    fn from_fugit_ticks(ticks: u64, nom: u32, denom: u32) -> Self {
+        let scaled = ticks as u128 * nom as u128 * T::get_tick_rate_hz() as u128;
+        let ticks = scaled.div_ceil(denom as u128);
+        Self::ticks(ticks.min(u32::MAX as u128) as u32)
+    }
+
+    #[cfg(feature = "fugit")]
+    fn to_fugit_ticks(self, nom: u32, denom: u32) -> u64 {
+        let scaled = self.ticks as u128 * denom as u128;
+        let ticks = scaled / (nom as u128 * T::get_tick_rate_hz() as u128);
+        ticks.min(u64::MAX as u128) as u64
     }
 }
 
@@ -78,3 +193,120 @@ where
         self.ticks
     }
 }
+
+impl<T> fmt::Debug for DurationImpl<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Duration")
+            .field("ticks", &self.ticks)
+            .finish()
+    }
+}
+
+impl<T> PartialEq for DurationImpl<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ticks == other.ticks
+    }
+}
+
+impl<T> Eq for DurationImpl<T> {}
+
+impl<T> PartialOrd for DurationImpl<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for DurationImpl<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.ticks.cmp(&other.ticks)
+    }
+}
+
+impl<T> Hash for DurationImpl<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.ticks.hash(state)
+    }
+}
+
+impl<T: FreeRtosTimeUnits + Copy> Add for DurationImpl<T> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::ticks(self.ticks.saturating_add(rhs.ticks))
+    }
+}
+
+impl<T: FreeRtosTimeUnits + Copy> AddAssign for DurationImpl<T> {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl<T: FreeRtosTimeUnits + Copy> Sub for DurationImpl<T> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::ticks(self.ticks.saturating_sub(rhs.ticks))
+    }
+}
+
+impl<T: FreeRtosTimeUnits + Copy> SubAssign for DurationImpl<T> {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl<T: FreeRtosTimeUnits + Copy> Mul<u32> for DurationImpl<T> {
+    type Output = Self;
+
+    fn mul(self, rhs: u32) -> Self {
+        Self::ticks(self.ticks.saturating_mul(rhs))
+    }
+}
+
+impl<T: FreeRtosTimeUnits + Copy> From<core::time::Duration> for DurationImpl<T> {
+    /// Rounded up to whole ticks.
+    fn from(duration: core::time::Duration) -> Self {
+        let scaled = duration.as_nanos() * T::get_tick_rate_hz() as u128;
+        let ticks = scaled.div_ceil(1_000_000_000);
+        Self::ticks(ticks.min(u32::MAX as u128) as u32)
+    }
+}
+
+impl<T: FreeRtosTimeUnits + Copy> From<DurationImpl<T>> for core::time::Duration {
+    fn from(duration: DurationImpl<T>) -> Self {
+        let nanos = duration.ticks as u128 * 1_000_000_000 / T::get_tick_rate_hz() as u128;
+        core::time::Duration::from_nanos(nanos as u64)
+    }
+}
+
+#[cfg(feature = "fugit")]
+macro_rules! impl_fugit_conversions {
+    ($($int:ty),*) => {$(
+        impl<T, const NOM: u32, const DENOM: u32> From<fugit::Duration<$int, NOM, DENOM>>
+            for DurationImpl<T>
+        where
+            T: FreeRtosTimeUnits + Copy,
+        {
+            /// Rounded up to whole ticks.
+            fn from(duration: fugit::Duration<$int, NOM, DENOM>) -> Self {
+                Self::from_fugit_ticks(duration.ticks() as u64, NOM, DENOM)
+            }
+        }
+
+        impl<T, const NOM: u32, const DENOM: u32> From<DurationImpl<T>>
+            for fugit::Duration<$int, NOM, DENOM>
+        where
+            T: FreeRtosTimeUnits + Copy,
+        {
+            /// Rounded down.
+            fn from(duration: DurationImpl<T>) -> Self {
+                let ticks = duration.to_fugit_ticks(NOM, DENOM).min(<$int>::MAX as u64);
+                Self::from_ticks(ticks as $int)
+            }
+        }
+    )*};
+}
+
+#[cfg(feature = "fugit")]
+impl_fugit_conversions!(u32, u64);
@@ -25,6 +25,7 @@ pub fn shim_sanity_check() -> Result<(), TypeSizeError> {
         (10, mem::size_of::<FreeRtosBaseType>()),
         (11, mem::size_of::<FreeRtosUBaseType>()),
         (12, mem::size_of::<FreeRtosTickType>()),
+        (13, mem::size_of::<FreeRtosEventBitsType>()),
         (20, mem::size_of::<FreeRtosTaskHandle>()),
         (21, mem::size_of::<FreeRtosQueueHandle>()),
         (22, mem::size_of::<FreeRtosSemaphoreHandle>()),
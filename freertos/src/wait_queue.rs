@@ -0,0 +1,141 @@
+use crate::base::*;
+use crate::base_type::v1::*;
+use crate::critical::*;
+use crate::instant::*;
+use crate::shim::*;
+use crate::units::*;
+
+struct Waiter {
+    task: FreeRtosTaskHandle,
+    notified: bool,
+    next: *mut Waiter,
+}
+
+/// FIFO list of tasks blocked on a synchronization primitive.
+///
+/// The nodes live on the stacks of the waiting tasks, which are woken through the
+/// task notification index reserved by the crate, so waiting needs no allocation.
+/// The list is only accessed inside a critical region and can't be used from an ISR.
+pub(crate) struct WaitQueue {
+    head: UnsafeCell<*mut Waiter>,
+}
+
+unsafe impl Send for WaitQueue {}
+unsafe impl Sync for WaitQueue {}
+
+impl WaitQueue {
+    pub(crate) const fn new() -> Self {
+        Self {
+            head: UnsafeCell::new(core::ptr::null_mut()),
+        }
+    }
+
+    /// Enqueue the current task, run `before_block` and block until the task is
+    /// notified or `max_wait` runs out. Returns `true` if the task was notified.
+    pub(crate) fn wait<D: DurationTicks>(&self, max_wait: D, before_block: impl FnOnce()) -> bool {
+        self.enqueue_and_block(max_wait, || false, before_block)
+    }
+
+    /// Block until the task is notified or `max_wait` runs out, unless `done` returns
+    /// `true`. `done` is checked inside the critical region that enqueues the task, so a
+    /// `notify_*` that follows the change `done` looks for is never missed.
+    pub(crate) fn wait_unless<D: DurationTicks>(
+        &self,
+        max_wait: D,
+        done: impl FnOnce() -> bool,
+    ) -> bool {
+        self.enqueue_and_block(max_wait, done, || {})
+    }
+
+    fn enqueue_and_block<D: DurationTicks>(
+        &self,
+        max_wait: D,
+        done: impl FnOnce() -> bool,
+        before_block: impl FnOnce(),
+    ) -> bool {
+        let budget = Deadline::after(max_wait);
+        let mut waiter = Waiter {
+            task: unsafe { freertos_rs_get_current_task() },
+            notified: false,
+            next: core::ptr::null_mut(),
+        };
+        let node: *mut Waiter = &mut waiter;
+
+        {
+            let _lock = CriticalRegion::enter();
+            if done() {
+                return true;
+            }
+            unsafe {
+                let mut link = self.head.get();
+                while !(*link).is_null() {
+                    link = &mut (**link).next;
+                }
+                *link = node;
+            }
+        }
+
+        before_block();
+
+        // Stale notifications from an earlier wait can wake us up early,
+        // only the flag set by `notify_*` counts.
+        loop {
+            let woken = unsafe { freertos_rs_sync_notify_take(budget.to_ticks()) } != 0;
+
+            let _lock = CriticalRegion::enter();
+            if unsafe { (*node).notified } {
+                return true;
+            }
+            if !woken {
+                self.unlink(node);
+                return false;
+            }
+        }
+    }
+
+    /// Wake the task that waits the longest. Returns `false` if no task was waiting.
+    pub(crate) fn notify_one(&self) -> bool {
+        let _lock = CriticalRegion::enter();
+        let head = unsafe { *self.head.get() };
+        if head.is_null() {
+            return false;
+        }
+        unsafe {
+            *self.head.get() = (*head).next;
+            (*head).notified = true;
+            freertos_rs_sync_notify_give((*head).task);
+        }
+        true
+    }
+
+    /// Wake all waiting tasks and return how many there were.
+    pub(crate) fn notify_all(&self) -> usize {
+        let _lock = CriticalRegion::enter();
+        let mut node = core::mem::replace(unsafe { &mut *self.head.get() }, core::ptr::null_mut());
+        let mut count = 0;
+        while !node.is_null() {
+            unsafe {
+                let next = (*node).next;
+                (*node).notified = true;
+                freertos_rs_sync_notify_give((*node).task);
+                node = next;
+            }
+            count += 1;
+        }
+        count
+    }
+
+    /// Must be called inside a critical region.
+    fn unlink(&self, node: *mut Waiter) {
+        unsafe {
+            let mut link = self.head.get();
+            while !(*link).is_null() {
+                if *link == node {
+                    *link = (*node).next;
+                    return;
+                }
+                link = &mut (**link).next;
+            }
+        }
+    }
+}
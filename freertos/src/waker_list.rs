@@ -0,0 +1,342 @@
+#[cfg(feature = "trace-facility")]
+use crate::base::*;
+use crate::base_type::v1::*;
+use crate::critical::*;
+use crate::isr::*;
+use crate::pend_call::*;
+use crate::shim::*;
+use core::future::poll_fn;
+use core::marker::PhantomPinned;
+use core::pin::{Pin, pin};
+use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+use core::task::{Poll, Waker};
+
+struct WakerNode {
+    waker: UnsafeCell<Option<Waker>>,
+    next: UnsafeCell<*const WakerNode>,
+    linked: UnsafeCell<bool>,
+    list: *const WakerList,
+    _pin: PhantomPinned,
+}
+
+// SAFETY: the fields are only accessed inside a critical region.
+unsafe impl Send for WakerNode {}
+unsafe impl Sync for WakerNode {}
+
+impl WakerNode {
+    fn new(list: &WakerList) -> Self {
+        Self {
+            waker: UnsafeCell::new(None),
+            next: UnsafeCell::new(core::ptr::null()),
+            linked: UnsafeCell::new(false),
+            list,
+            _pin: PhantomPinned,
+        }
+    }
+}
+
+impl Drop for WakerNode {
+    fn drop(&mut self) {
+        let list = unsafe { &*self.list };
+        let _waker = {
+            let _lock = CriticalRegion::enter();
+            list.unlink(self);
+            self.waker.get_mut().take()
+        };
+    }
+}
+
+/// A critical region that can also be entered from an ISR.
+struct AnyCriticalRegion(u32);
+
+impl AnyCriticalRegion {
+    fn enter() -> Self {
+        if is_in_isr() {
+            Self(unsafe { freertos_rs_enter_critical_from_isr() })
+        } else {
+            unsafe { freertos_rs_enter_critical() };
+            Self(0)
+        }
+    }
+}
+
+impl Drop for AnyCriticalRegion {
+    fn drop(&mut self) {
+        if is_in_isr() {
+            unsafe { freertos_rs_exit_critical_from_isr(self.0) }
+        } else {
+            unsafe { freertos_rs_exit_critical() }
+        }
+    }
+}
+
+/// FIFO list of futures waiting for a kernel object, the async counterpart of `WaitQueue`.
+///
+/// The nodes live in the pinned futures, so registering needs no allocation. The
+/// list is only accessed inside a critical region, wakers are dropped and woken
+/// outside of it.
+pub(crate) struct WakerList {
+    head: UnsafeCell<*const WakerNode>,
+    len: AtomicUsize,
+}
+
+unsafe impl Send for WakerList {}
+unsafe impl Sync for WakerList {}
+
+impl fmt::Debug for WakerList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} waiting", self.len.load(Ordering::Relaxed))
+    }
+}
+
+impl WakerList {
+    pub(crate) const fn new() -> Self {
+        Self {
+            head: UnsafeCell::new(core::ptr::null()),
+            len: AtomicUsize::new(0),
+        }
+    }
+
+    /// Wait until `ready` returns a value. `ready` must not block, it is tried again
+    /// every time the future is woken by [`wake_all`](WakerList::wake_all).
+    pub(crate) async fn wait_for<R>(&self, ready: impl FnMut() -> Option<R>) -> R {
+        self.wait_for_with(ready, |_| ()).await
+    }
+
+    /// Like [`wait_for`](WakerList::wait_for), `registered` is called every time the
+    /// future is linked into the list, before `ready` is tried again.
+    async fn wait_for_with<R>(
+        &self,
+        mut ready: impl FnMut() -> Option<R>,
+        mut registered: impl FnMut(&Waker),
+    ) -> R {
+        let node = pin!(WakerNode::new(self));
+        poll_fn(|cx| {
+            if let Some(value) = ready() {
+                return Poll::Ready(value);
+            }
+            self.register(node.as_ref(), cx.waker());
+            registered(cx.waker());
+            // Whoever made `ready` succeed in the meantime saw the node linked.
+            match ready() {
+                Some(value) => Poll::Ready(value),
+                None => Poll::Pending,
+            }
+        })
+        .await
+    }
+
+    fn register(&self, node: Pin<&WakerNode>, waker: &Waker) {
+        let node = node.get_ref();
+        let waker = Some(waker.clone());
+        let _old = {
+            let _lock = CriticalRegion::enter();
+            unsafe {
+                if !*node.linked.get() {
+                    let mut link = self.head.get();
+                    while !(*link).is_null() {
+                        link = (**link).next.get();
+                    }
+                    *link = node;
+                    *node.next.get() = core::ptr::null();
+                    *node.linked.get() = true;
+                    self.len.fetch_add(1, Ordering::SeqCst);
+                }
+                mem::replace(&mut *node.waker.get(), waker)
+            }
+        };
+    }
+
+    /// Wake the futures that are waiting now. Futures that register again while
+    /// this runs, because they were polled right away by a higher priority task,
+    /// are left for the next call.
+    pub(crate) fn wake_all(&self) {
+        let mut count = self.len.load(Ordering::SeqCst);
+        while count > 0 {
+            count -= 1;
+            let waker = {
+                let _lock = AnyCriticalRegion::enter();
+                let head = unsafe { *self.head.get() };
+                if head.is_null() {
+                    return;
+                }
+                unsafe {
+                    self.unlink(&*head);
+                    (*(*head).waker.get()).take()
+                }
+            };
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+        }
+    }
+
+    /// Must be called inside a critical region.
+    fn unlink(&self, node: &WakerNode) {
+        unsafe {
+            if !*node.linked.get() {
+                return;
+            }
+            let mut link = self.head.get();
+            while !(*link).is_null() {
+                if core::ptr::eq(*link, node) {
+                    *link = *node.next.get();
+                    break;
+                }
+                link = (**link).next.get();
+            }
+            *node.linked.get() = false;
+        }
+        self.len.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// A [`WakerList`] shared with calls pended from an ISR, allocated on the first async
+/// wait. Kernel objects that are never awaited, or are only used from C, don't need
+/// the heap for it.
+pub(crate) struct SharedWakerList {
+    list: AtomicPtr<WakerList>,
+    /// A change was queued from an ISR before the list existed, see
+    /// [`wake_all_after_from_isr`](SharedWakerList::wake_all_after_from_isr).
+    deferred: AtomicBool,
+}
+
+impl fmt::Debug for SharedWakerList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.get() {
+            Some(list) => list.fmt(f),
+            None => write!(f, "0 waiting"),
+        }
+    }
+}
+
+impl SharedWakerList {
+    pub(crate) const fn new() -> Self {
+        Self {
+            list: AtomicPtr::new(core::ptr::null_mut()),
+            deferred: AtomicBool::new(false),
+        }
+    }
+
+    fn get(&self) -> Option<&WakerList> {
+        unsafe { self.list.load(Ordering::Acquire).as_ref() }
+    }
+
+    /// A new reference to the list, for a call pended from an ISR.
+    fn get_shared(&self) -> Option<Arc<WakerList>> {
+        let list = self.list.load(Ordering::Acquire);
+        if list.is_null() {
+            return None;
+        }
+        // SAFETY: `list` came from `Arc::into_raw` and `self` holds a reference to it.
+        unsafe {
+            Arc::increment_strong_count(list);
+            Some(Arc::from_raw(list))
+        }
+    }
+
+    fn get_or_alloc(&self) -> &WakerList {
+        if let Some(list) = self.get() {
+            return list;
+        }
+        let new = Arc::into_raw(Arc::new(WakerList::new())) as *mut WakerList;
+        match self.list.compare_exchange(
+            core::ptr::null_mut(),
+            new,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            Ok(_) => unsafe { &*new },
+            Err(current) => {
+                drop(unsafe { Arc::from_raw(new) });
+                unsafe { &*current }
+            }
+        }
+    }
+
+    /// Like [`WakerList::wait_for`], allocates the list on the first call.
+    pub(crate) async fn wait_for<R>(&self, ready: impl FnMut() -> Option<R>) -> R {
+        self.get_or_alloc()
+            .wait_for_with(ready, |waker| self.wake_deferred(waker))
+            .await
+    }
+
+    /// Wake the list behind a change that an ISR queued before the list existed, so
+    /// a future that registered in the meantime isn't left waiting.
+    fn wake_deferred(&self, waker: &Waker) {
+        if !self.deferred.swap(false, Ordering::SeqCst) {
+            return;
+        }
+        let Some(list) = self.get_shared() else {
+            return;
+        };
+        if pend_function_call(move || list.wake_all()).is_err() {
+            // Poll again until the wake can be queued.
+            self.deferred.store(true, Ordering::SeqCst);
+            waker.wake_by_ref();
+        }
+    }
+
+    pub(crate) fn wake_all(&self) {
+        if let Some(list) = self.get() {
+            list.wake_all();
+        }
+    }
+
+    /// Wake the waiting futures from the timer daemon task, to keep the ISR short.
+    ///
+    /// If the call can't be deferred, because all [`PEND_CALL_SLOTS`] are taken or the
+    /// timer command queue is full, the futures are woken right away instead of being
+    /// lost. This is only correct for changes that are already visible when the ISR
+    /// returns, like an item sent to a queue. The wakers of [`Executor`](crate::Executor)
+    /// work in an ISR, wakers of other executors have to as well.
+    pub(crate) fn wake_all_from_isr(&self, context: &mut InterruptContext) {
+        let Some(list) = self.get_shared() else {
+            return;
+        };
+        if list.len.load(Ordering::SeqCst) == 0 {
+            return;
+        }
+        let shared = list.clone();
+        if pend_function_call_from_isr(context, move || shared.wake_all()).is_err() {
+            list.wake_all();
+        }
+    }
+
+    /// Queue a change in the timer daemon task and wake the waiting futures after it,
+    /// for changes an ISR can't make itself, like setting event group bits.
+    ///
+    /// Once a future waited, `apply` and the wake are pended together, which fails with
+    /// [`FreeRtosError::QueueFull`] if that isn't possible, without applying the change.
+    /// Before that, `apply_from_isr` queues the change and a future that registers
+    /// later wakes itself behind it.
+    #[cfg(feature = "trace-facility")]
+    pub(crate) fn wake_all_after_from_isr(
+        &self,
+        context: &mut InterruptContext,
+        apply: impl FnOnce() + Send + 'static,
+        apply_from_isr: impl FnOnce() -> Result<(), FreeRtosError>,
+    ) -> Result<(), FreeRtosError> {
+        match self.get_shared() {
+            Some(list) => pend_function_call_from_isr(context, move || {
+                apply();
+                list.wake_all();
+            })
+            .map_err(|_| FreeRtosError::QueueFull),
+            None => {
+                apply_from_isr()?;
+                self.deferred.store(true, Ordering::SeqCst);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Drop for SharedWakerList {
+    fn drop(&mut self) {
+        let list = *self.list.get_mut();
+        if !list.is_null() {
+            drop(unsafe { Arc::from_raw(list) });
+        }
+    }
+}
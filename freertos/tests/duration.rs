@@ -0,0 +1,77 @@
+use freertos_next::{DurationImpl, DurationTicks, FreeRtosTimeUnits};
+
+/// A 100 Hz tick, so that one tick is 10 ms.
+#[derive(Copy, Clone)]
+struct Units100Hz;
+
+impl FreeRtosTimeUnits for Units100Hz {
+    fn get_tick_period_ms() -> u32 {
+        10
+    }
+    fn get_max_wait() -> u32 {
+        u32::MAX
+    }
+}
+
+type Duration = DurationImpl<Units100Hz>;
+
+#[test]
+fn rounding() {
+    assert_eq!(Duration::ms(5).to_ticks(), 1);
+    assert_eq!(Duration::ms_round_up(20).to_ticks(), 2);
+    assert_eq!(Duration::ms_round_up(21).to_ticks(), 3);
+    assert_eq!(Duration::ms_round_down(5).to_ticks(), 0);
+    assert_eq!(Duration::ms_round_down(29).to_ticks(), 2);
+    assert_eq!(Duration::us(1).to_ticks(), 1);
+    assert_eq!(Duration::us_round_down(19_999).to_ticks(), 1);
+    assert_eq!(Duration::secs(3).to_ticks(), 300);
+    assert_eq!(Duration::ms(0), Duration::zero());
+}
+
+#[test]
+fn saturation() {
+    assert_eq!(Duration::secs(u32::MAX), Duration::infinite());
+    assert_eq!(Duration::ms(u32::MAX).to_ticks(), 429_496_730);
+    assert_eq!(Duration::infinite().to_ms(), u32::MAX);
+    assert_eq!(Duration::eps() - Duration::ms(100), Duration::zero());
+    assert_eq!(Duration::infinite() + Duration::eps(), Duration::infinite());
+    assert_eq!(Duration::secs(1) * u32::MAX, Duration::infinite());
+}
+
+#[test]
+fn arithmetic_and_ordering() {
+    let mut d = Duration::ms(30);
+    d += Duration::ms(20);
+    assert_eq!(d, Duration::ms(50));
+    d -= Duration::ms(10);
+    assert_eq!(d * 2, Duration::ms(80));
+    assert!(Duration::ms(10) < Duration::ms(11));
+    assert_eq!(Duration::ms(11).max(Duration::secs(1)), Duration::secs(1));
+    assert_eq!(Duration::secs(2).to_us(), 2_000_000);
+}
+
+#[test]
+fn core_duration() {
+    let d: Duration = core::time::Duration::from_micros(10_001).into();
+    assert_eq!(d.to_ticks(), 2);
+    let d: Duration = core::time::Duration::from_nanos(1).into();
+    assert_eq!(d, Duration::eps());
+    let back: core::time::Duration = Duration::ms(250).into();
+    assert_eq!(back, core::time::Duration::from_millis(250));
+}
+
+#[cfg(feature = "fugit")]
+#[test]
+fn fugit_duration() {
+    use fugit::{MicrosDurationU32, MillisDurationU64};
+
+    assert_eq!(
+        Duration::from_fugit(MicrosDurationU32::micros(10_001)).to_ticks(),
+        2
+    );
+    let d: Duration = MillisDurationU64::millis(1_000).into();
+    assert_eq!(d, Duration::secs(1));
+    let back: MillisDurationU64 = Duration::ms(250).into();
+    assert_eq!(back, MillisDurationU64::millis(250));
+    assert_eq!(Duration::ms(15).to_fugit::<1, 1_000>().ticks(), 20);
+}